@@ -561,6 +561,38 @@ impl AddressBook {
         result
     }
 
+    /// Replaces all public key addresses in text with their plain, uncolored labels.
+    ///
+    /// This is useful for matching text against labels, e.g. in log assertions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use solana_address_book::AddressBook;
+    /// use anchor_lang::prelude::*;
+    ///
+    /// let mut book = AddressBook::new();
+    /// let wallet = Pubkey::new_unique();
+    /// book.add_wallet(wallet, "alice".to_string()).unwrap();
+    ///
+    /// let text = format!("Transfer to {}", wallet);
+    /// assert_eq!(book.replace_addresses_with_labels(&text), "Transfer to alice");
+    /// ```
+    pub fn replace_addresses_with_labels(&self, text: &str) -> String {
+        let mut result = text.to_string();
+
+        // Sort by pubkey string length (longest first) to avoid partial replacements
+        let mut sorted_addresses: Vec<_> = self.addresses.iter().collect();
+        sorted_addresses.sort_by_key(|(pubkey, _)| std::cmp::Reverse(pubkey.to_string().len()));
+
+        for (pubkey, registered_addresses) in sorted_addresses {
+            if let Some((label, _)) = registered_addresses.first() {
+                result = result.replace(&pubkey.to_string(), label);
+            }
+        }
+        result
+    }
+
     /// Prints all addresses in the address book with colored formatting.
     ///
     /// Addresses are grouped by role type and displayed with appropriate colors.
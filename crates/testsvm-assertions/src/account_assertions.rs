@@ -0,0 +1,157 @@
+//! # Account Assertions
+//!
+//! Assertions on the deserialized state of Anchor accounts.
+//!
+//! Comparing fields loaded with [AccountRef::load] using `assert_eq!` reports raw pubkeys
+//! and no context about the rest of the account. These assertions instead print the account
+//! field by field on a mismatch, with addresses replaced by their labels from the
+//! [AddressBook] and the differing fields highlighted.
+//!
+//! Anchor accounts generated by `declare_program!` implement [Debug] but not [PartialEq], so
+//! accounts are compared by their pretty-printed [Debug] representations.
+
+use std::fmt::Debug;
+
+use anyhow::*;
+use colored::Colorize;
+use testsvm_core::prelude::*;
+
+/// Assertions on the state of an account, loaded with [TestSVMAccountAssertions::assert_account].
+pub struct AccountAssertions<'a, T> {
+    /// Address book used to label addresses in mismatches
+    address_book: &'a AddressBook,
+    /// Address of the account
+    pubkey: Pubkey,
+    /// The loaded account state
+    pub state: T,
+}
+
+impl<T: Debug> AccountAssertions<'_, T> {
+    /// Asserts that a field of the account has the expected value.
+    ///
+    /// On a mismatch, the expected and actual values are printed along with the full account.
+    pub fn field<V: PartialEq + Debug>(self, get: impl Fn(&T) -> V, expected: V) -> Result<Self> {
+        let actual = get(&self.state);
+        if actual == expected {
+            return Ok(self);
+        }
+
+        let label = self.address_book.get_label(&self.pubkey);
+        println!(
+            "\n{} {}",
+            "Field mismatch on account".red().bold(),
+            self.address_book.format_address(&self.pubkey)
+        );
+        println!(
+            "  {} {}",
+            "expected:".green(),
+            self.address_book
+                .replace_addresses_in_text(&format!("{expected:?}"))
+        );
+        println!(
+            "  {} {}",
+            "actual:".red(),
+            self.address_book
+                .replace_addresses_in_text(&format!("{actual:?}"))
+        );
+        println!(
+            "{}",
+            self.address_book
+                .replace_addresses_in_text(&format!("{:#?}", self.state))
+        );
+        Err(anyhow!(
+            "Field mismatch on account {label}: expected {expected:?}, got {actual:?}"
+        ))
+    }
+
+    /// Asserts that every field of the account equals the expected state.
+    ///
+    /// On a mismatch, a field-by-field diff of the two states is printed.
+    pub fn equals(self, expected: &T) -> Result<Self> {
+        let expected = format!("{expected:#?}");
+        let actual = format!("{:#?}", self.state);
+        if actual == expected {
+            return Ok(self);
+        }
+
+        let label = self.address_book.get_label(&self.pubkey);
+        println!(
+            "\n{} {} {}",
+            "Account mismatch on".red().bold(),
+            self.address_book.format_address(&self.pubkey),
+            format!("({} expected, {} actual)", "-".green(), "+".red()).dimmed()
+        );
+        print_state_diff(self.address_book, &expected, &actual);
+        Err(anyhow!("Account {label} does not match the expected state"))
+    }
+}
+
+/// Prints a line-by-line diff of two pretty-printed account states.
+fn print_state_diff(address_book: &AddressBook, expected: &str, actual: &str) {
+    let expected_lines: Vec<_> = expected.lines().collect();
+    let actual_lines: Vec<_> = actual.lines().collect();
+    for index in 0..expected_lines.len().max(actual_lines.len()) {
+        let expected_line = expected_lines.get(index);
+        let actual_line = actual_lines.get(index);
+        if expected_line == actual_line {
+            if let Some(line) = expected_line {
+                println!("  {}", address_book.replace_addresses_in_text(line).dimmed());
+            }
+            continue;
+        }
+        if let Some(line) = expected_line {
+            println!("{} {}", "-".green(), address_book.replace_addresses_in_text(line));
+        }
+        if let Some(line) = actual_line {
+            println!("{} {}", "+".red(), address_book.replace_addresses_in_text(line));
+        }
+    }
+}
+
+/// Extension trait for asserting on the state of accounts in a [TestSVM].
+pub trait TestSVMAccountAssertions {
+    /// Loads an account for assertions on its state, failing if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// env.assert_account(&miner)?
+    ///     .field(|m| m.balance, 100)?
+    ///     .field(|m| m.authority, user.pubkey())?;
+    /// ```
+    fn assert_account<T: AccountDeserialize + Debug>(
+        &self,
+        account: &AccountRef<T>,
+    ) -> Result<AccountAssertions<'_, T>>;
+}
+
+impl TestSVMAccountAssertions for TestSVM {
+    fn assert_account<T: AccountDeserialize + Debug>(
+        &self,
+        account: &AccountRef<T>,
+    ) -> Result<AccountAssertions<'_, T>> {
+        let state = account.load(self).with_context(|| {
+            format!(
+                "Failed to load account {}",
+                self.address_book.get_label(&account.key)
+            )
+        })?;
+        Ok(AccountAssertions {
+            address_book: &self.address_book,
+            pubkey: account.key,
+            state,
+        })
+    }
+}
+
+/// Asserts that every field of an account equals the expected state.
+///
+/// See [AccountAssertions::equals].
+pub fn assert_account_eq<T: AccountDeserialize + Debug>(
+    env: &TestSVM,
+    account: &AccountRef<T>,
+    expected: &T,
+) -> Result<()> {
+    env.assert_account(account)?.equals(expected)?;
+    Ok(())
+}
@@ -0,0 +1,181 @@
+//! # Balance Assertions
+//!
+//! Assertions on the SOL and token balance changes of a successful transaction.
+//!
+//! Single balances can be checked with [TXSuccessAssertions::lamports_changed] and
+//! [TXSuccessAssertions::token_balance_changed]. To check that a transaction moved exactly
+//! the expected balances and nothing else, list every expected change with
+//! [TXSuccessAssertions::expect_balance_changes] and call [BalanceChangeAssertions::verify].
+
+use anyhow::*;
+use colored::Colorize;
+use testsvm_core::prelude::*;
+
+use crate::TXSuccessAssertions;
+
+impl TXSuccessAssertions {
+    /// Asserts that the lamports of an account changed by exactly `delta`.
+    ///
+    /// `delta` is negative if the balance decreased. Fails if the account was not part of
+    /// the transaction.
+    pub fn lamports_changed(&self, pubkey: &Pubkey, delta: i128) -> Result<&Self> {
+        let address_book = &self.success.address_book;
+        let balance = self.balances().lamports(pubkey).ok_or_else(|| {
+            anyhow!(
+                "Account {} was not part of the transaction",
+                address_book.get_label(pubkey)
+            )
+        })?;
+        if balance.change() != delta {
+            bail!(
+                "Expected the lamports of {} to change by {delta}, but they changed by {}",
+                address_book.get_label(pubkey),
+                balance.change()
+            );
+        }
+        Ok(self)
+    }
+
+    /// Asserts that the raw token amount of a token account changed by exactly `delta`.
+    ///
+    /// `delta` is negative if the balance decreased. Fails if the token account was not part
+    /// of the transaction.
+    pub fn token_balance_changed(&self, token_account: &impl Key, delta: i128) -> Result<&Self> {
+        let address_book = &self.success.address_book;
+        let pubkey = token_account.key();
+        let balance = self.balances().token(&pubkey).ok_or_else(|| {
+            anyhow!(
+                "Token account {} was not part of the transaction",
+                address_book.get_label(&pubkey)
+            )
+        })?;
+        if balance.change() != delta {
+            bail!(
+                "Expected the token balance of {} to change by {delta}, but it changed by {}",
+                address_book.get_label(&pubkey),
+                balance.change()
+            );
+        }
+        Ok(self)
+    }
+
+    /// Starts a list of the exact balance changes expected from the transaction.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// result
+    ///     .succeeds()?
+    ///     .expect_balance_changes()
+    ///     .tokens(&user_tokens, -100)
+    ///     .tokens(&vault, 100)
+    ///     .verify()?;
+    /// ```
+    pub fn expect_balance_changes(&self) -> BalanceChangeAssertions<'_> {
+        BalanceChangeAssertions {
+            assertions: self,
+            lamports: Vec::new(),
+            tokens: Vec::new(),
+            ignored: Vec::new(),
+        }
+    }
+}
+
+/// The exact set of balance changes expected from a transaction.
+///
+/// Created with [TXSuccessAssertions::expect_balance_changes]. [BalanceChangeAssertions::verify]
+/// fails if any listed balance changed by a different amount, or if any balance that was not
+/// listed changed at all. The fee payer's lamports are ignored unless they are listed, since
+/// they always change by the transaction fee.
+pub struct BalanceChangeAssertions<'a> {
+    assertions: &'a TXSuccessAssertions,
+    lamports: Vec<(Pubkey, i128)>,
+    tokens: Vec<(Pubkey, i128)>,
+    ignored: Vec<Pubkey>,
+}
+
+impl BalanceChangeAssertions<'_> {
+    /// Expects the lamports of an account to change by exactly `delta`.
+    pub fn lamports(mut self, pubkey: &Pubkey, delta: i128) -> Self {
+        self.lamports.push((*pubkey, delta));
+        self
+    }
+
+    /// Expects the raw token amount of a token account to change by exactly `delta`.
+    pub fn tokens(mut self, token_account: &impl Key, delta: i128) -> Self {
+        self.tokens.push((token_account.key(), delta));
+        self
+    }
+
+    /// Allows the lamports of an account to change by any amount.
+    pub fn ignore_lamports(mut self, pubkey: &Pubkey) -> Self {
+        self.ignored.push(*pubkey);
+        self
+    }
+
+    /// Checks the balance changes of the transaction against the expected changes.
+    ///
+    /// Every mismatch is printed with labeled addresses before returning an error.
+    pub fn verify(self) -> Result<()> {
+        let success = &self.assertions.success;
+        let address_book = &success.address_book;
+        let balances = &success.balances;
+        let fee_payer = success.transaction.message.static_account_keys()[0];
+        let mut mismatches = Vec::new();
+
+        for (pubkey, delta) in &self.lamports {
+            let change = balances.lamports(pubkey).map_or(0, |balance| balance.change());
+            if change != *delta {
+                mismatches.push(format!(
+                    "SOL {}: expected {delta}, changed by {change}",
+                    address_book.format_address(pubkey)
+                ));
+            }
+        }
+        for balance in &balances.lamports {
+            let expected = self.lamports.iter().any(|(pubkey, _)| *pubkey == balance.pubkey);
+            let ignored = self.ignored.contains(&balance.pubkey)
+                || (balance.pubkey == fee_payer && !expected);
+            if !expected && !ignored && balance.change() != 0 {
+                mismatches.push(format!(
+                    "SOL {}: unexpectedly changed by {}",
+                    address_book.format_address(&balance.pubkey),
+                    balance.change()
+                ));
+            }
+        }
+
+        for (pubkey, delta) in &self.tokens {
+            let change = balances.token(pubkey).map_or(0, |balance| balance.change());
+            if change != *delta {
+                mismatches.push(format!(
+                    "Token {}: expected {delta}, changed by {change}",
+                    address_book.format_address(pubkey)
+                ));
+            }
+        }
+        for balance in &balances.tokens {
+            let expected = self.tokens.iter().any(|(pubkey, _)| *pubkey == balance.pubkey);
+            if !expected && balance.change() != 0 {
+                mismatches.push(format!(
+                    "Token {}: unexpectedly changed by {}",
+                    address_book.format_address(&balance.pubkey),
+                    balance.change()
+                ));
+            }
+        }
+
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+        println!("\n{}", "Unexpected balance changes:".red().bold());
+        for mismatch in &mismatches {
+            println!("   {mismatch}");
+        }
+        balances.print(address_book);
+        Err(anyhow!(
+            "{} unexpected balance change(s) in the transaction",
+            mismatches.len()
+        ))
+    }
+}
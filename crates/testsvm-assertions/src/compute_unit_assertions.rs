@@ -0,0 +1,170 @@
+//! # Compute Unit Assertions
+//!
+//! Assertions that keep the compute units of transactions from regressing.
+//!
+//! [TXSuccessAssertions::with_max_compute_units] enforces a hard ceiling.
+//! [TXSuccessAssertions::assert_compute_units_snapshot] instead compares the compute units of a
+//! named transaction against a golden file, `compute_units.json` in the root of the crate
+//! being tested, which should be checked in. The assertion fails if usage grows by more than
+//! a tolerance, so regressions are caught in code review rather than on mainnet.
+//!
+//! Run the tests with `TESTSVM_UPDATE_COMPUTE_UNITS=1` to record new transactions or accept
+//! changes in usage:
+//!
+//! ```bash
+//! TESTSVM_UPDATE_COMPUTE_UNITS=1 cargo test
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::*;
+use colored::Colorize;
+
+use crate::TXSuccessAssertions;
+
+/// Environment variable that enables updating the compute unit golden file.
+pub const UPDATE_COMPUTE_UNITS_ENV: &str = "TESTSVM_UPDATE_COMPUTE_UNITS";
+
+/// Default percentage by which compute units may grow before a snapshot assertion fails.
+pub const DEFAULT_COMPUTE_UNITS_TOLERANCE_PERCENT: u64 = 2;
+
+/// Serializes access to the golden file, since tests run in parallel.
+static GOLDEN_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+impl TXSuccessAssertions {
+    /// Asserts that the transaction consumed at most `max_compute_units`.
+    pub fn with_max_compute_units(&self, max_compute_units: u64) -> Result<&Self> {
+        let compute_units = self.compute_units();
+        if compute_units > max_compute_units {
+            bail!(
+                "Transaction consumed {compute_units} compute units, exceeding the maximum of {max_compute_units}"
+            );
+        }
+        Ok(self)
+    }
+
+    /// Asserts that the compute units of the transaction have not grown beyond the value
+    /// recorded for `name` in the golden file, with the default tolerance.
+    ///
+    /// The golden file is `compute_units.json` in the root of the crate being tested. Run the
+    /// tests with [UPDATE_COMPUTE_UNITS_ENV] set to record new transactions or accept changes.
+    pub fn assert_compute_units_snapshot(&self, name: &str) -> Result<&Self> {
+        self.assert_compute_units_snapshot_with_tolerance(
+            name,
+            DEFAULT_COMPUTE_UNITS_TOLERANCE_PERCENT,
+        )
+    }
+
+    /// Asserts that the compute units of the transaction have not grown by more than
+    /// `tolerance_percent` over the value recorded for `name` in the golden file.
+    pub fn assert_compute_units_snapshot_with_tolerance(
+        &self,
+        name: &str,
+        tolerance_percent: u64,
+    ) -> Result<&Self> {
+        let update = env::var(UPDATE_COMPUTE_UNITS_ENV).is_ok_and(|value| value != "0");
+        check_compute_units_snapshot(
+            &golden_file_path()?,
+            name,
+            self.compute_units(),
+            tolerance_percent,
+            update,
+        )?;
+        Ok(self)
+    }
+}
+
+/// Returns the path of the golden file of the crate being tested.
+fn golden_file_path() -> Result<PathBuf> {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR")
+        .map_err(|e| anyhow!("Failed to get environment variable `CARGO_MANIFEST_DIR`: {e}"))?;
+    Ok(PathBuf::from(manifest_dir).join("compute_units.json"))
+}
+
+/// Compares compute units against the golden file at `path`, or records them if `update` is set.
+fn check_compute_units_snapshot(
+    path: &Path,
+    name: &str,
+    compute_units: u64,
+    tolerance_percent: u64,
+    update: bool,
+) -> Result<()> {
+    let _lock = GOLDEN_FILE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut snapshots: BTreeMap<String, u64> = if path.exists() {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("Invalid {}", path.display()))?
+    } else {
+        BTreeMap::new()
+    };
+
+    if update {
+        if snapshots.get(name) != Some(&compute_units) {
+            snapshots.insert(name.to_string(), compute_units);
+            let contents = serde_json::to_string_pretty(&snapshots)?;
+            fs::write(path, format!("{contents}\n"))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        return Ok(());
+    }
+
+    let Some(&recorded) = snapshots.get(name) else {
+        bail!(
+            "No compute units recorded for '{name}' in {}. Run with {UPDATE_COMPUTE_UNITS_ENV}=1 to record them.",
+            path.display()
+        );
+    };
+    let limit = recorded + recorded * tolerance_percent / 100;
+    if compute_units > limit {
+        bail!(
+            "Compute units of '{name}' grew from {recorded} to {compute_units}, beyond the {tolerance_percent}% tolerance. Run with {UPDATE_COMPUTE_UNITS_ENV}=1 to accept the change."
+        );
+    }
+    if compute_units < recorded {
+        println!(
+            "{} '{name}' now uses {compute_units} compute units, down from {recorded}. Run with {UPDATE_COMPUTE_UNITS_ENV}=1 to lock in the improvement.",
+            "📉".green(),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_golden_file() -> PathBuf {
+        env::temp_dir().join(format!(
+            "testsvm-compute-units-{}.json",
+            solana_sdk::pubkey::Pubkey::new_unique()
+        ))
+    }
+
+    #[test]
+    fn test_compute_units_snapshot() -> Result<()> {
+        let path = temp_golden_file();
+
+        // Missing snapshots fail unless updating
+        assert!(check_compute_units_snapshot(&path, "stake", 1_000, 2, false).is_err());
+        check_compute_units_snapshot(&path, "stake", 1_000, 2, true)?;
+        let snapshots: BTreeMap<String, u64> = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        assert_eq!(snapshots["stake"], 1_000);
+
+        // Growth within the tolerance and improvements pass
+        check_compute_units_snapshot(&path, "stake", 1_020, 2, false)?;
+        check_compute_units_snapshot(&path, "stake", 900, 2, false)?;
+
+        // Growth beyond the tolerance fails
+        assert!(check_compute_units_snapshot(&path, "stake", 1_021, 2, false).is_err());
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
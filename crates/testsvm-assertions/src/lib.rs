@@ -10,14 +10,37 @@
 //! ## Features
 //!
 //! - **Success/Failure Assertions**: Verify transactions succeed or fail as expected
-//! - **Error Matching**: Check for specific error types including Anchor errors
+//! - **Error Matching**: Check for specific error types including typed Anchor error codes
+//! - **Transaction Errors**: Match fee, rent, blockhash and other runtime failures
+//! - **Error Origin**: Check which instruction, account and constraint values caused a failure
+//! - **Log Matching**: Match raw or labeled log lines by substring, regex, program and CPI depth
+//! - **Balance Changes**: Check exactly which SOL and token balances a transaction moved
+//! - **Compute Units**: Enforce compute unit ceilings and catch regressions against a golden file
+//! - **Account State**: Assert on deserialized Anchor accounts with labeled, field-by-field diffs
 //! - **Type-safe API**: Compile-time guarantees for assertion chains
 
+use std::fmt::Debug;
+
 use anyhow::*;
-use litesvm::types::TransactionMetadata;
 use solana_sdk::{instruction::InstructionError, program_error::ProgramError};
 use testsvm_core::prelude::*;
 
+mod account_assertions;
+pub use account_assertions::{AccountAssertions, TestSVMAccountAssertions, assert_account_eq};
+
+mod transaction_error_assertions;
+
+mod log_assertions;
+pub use log_assertions::LogAssertions;
+
+mod balance_assertions;
+pub use balance_assertions::BalanceChangeAssertions;
+
+mod compute_unit_assertions;
+pub use compute_unit_assertions::{
+    DEFAULT_COMPUTE_UNITS_TOLERANCE_PERCENT, UPDATE_COMPUTE_UNITS_ENV,
+};
+
 /// Provides assertion methods for failed transactions.
 ///
 /// This struct wraps a transaction error and provides helper methods
@@ -71,6 +94,66 @@ impl TXErrorAssertions {
         }
     }
 
+    /// Asserts that the transaction failed with a specific variant of an Anchor error enum.
+    ///
+    /// Works with any enum defined with `#[error_code]`, as well as Anchor's built-in
+    /// `anchor_lang::error::ErrorCode`. The custom error code of the transaction is compared
+    /// against the code of the variant, which includes the error code offset, and the name
+    /// logged by Anchor is then checked against the name of the variant.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// result
+    ///     .fails()?
+    ///     .with_anchor_error_code(anchor_lang::error::ErrorCode::AccountNotInitialized)?;
+    /// ```
+    pub fn with_anchor_error_code<E: Into<u32> + Debug + Copy>(&self, error: E) -> Result<()> {
+        let error_name = format!("{error:?}");
+        let error_code: u32 = error.into();
+        match self.error.metadata.err.clone() {
+            solana_sdk::transaction::TransactionError::InstructionError(
+                _,
+                InstructionError::Custom(code),
+            ) => {
+                if code != error_code {
+                    return Err(anyhow!(
+                        "Expected Anchor error {} ({}), got error number {}",
+                        error_name,
+                        error_code,
+                        code
+                    ));
+                }
+                match self.error.anchor_error() {
+                    Some(anchor_error)
+                        if anchor_error.error_name == error_name
+                            && anchor_error.error_number == error_code =>
+                    {
+                        Ok(())
+                    }
+                    Some(anchor_error) => Err(anyhow!(
+                        "Expected Anchor error {} ({}), got {} ({})",
+                        error_name,
+                        error_code,
+                        anchor_error.error_name,
+                        anchor_error.error_number
+                    )),
+                    None => Err(anyhow!(
+                        "Expected Anchor error {} ({}), but nothing was found in the logs",
+                        error_name,
+                        error_code
+                    )),
+                }
+            }
+            _ => Err(anyhow!(
+                "Expected Anchor error {} ({}), but got '{}'",
+                error_name,
+                error_code,
+                self.error.metadata.err.to_string()
+            )),
+        }
+    }
+
     /// Asserts that the transaction failed with a specific error message.
     ///
     /// This method checks the transaction logs for an error message containing
@@ -152,6 +235,92 @@ impl TXErrorAssertions {
         }
     }
 
+    /// Asserts that the transaction failed in the top-level instruction at `index`.
+    pub fn at_instruction(&self, index: usize) -> Result<&Self> {
+        match &self.error.metadata.err {
+            solana_sdk::transaction::TransactionError::InstructionError(
+                instruction_index,
+                _,
+            ) if *instruction_index as usize == index => Ok(self),
+            solana_sdk::transaction::TransactionError::InstructionError(
+                instruction_index,
+                _,
+            ) => Err(anyhow!(
+                "Expected instruction {} to fail, but instruction {} failed",
+                index,
+                instruction_index
+            )),
+            err => Err(anyhow!(
+                "Expected instruction {} to fail, but got '{}'",
+                index,
+                err
+            )),
+        }
+    }
+
+    /// Returns the Anchor error logged by the failing program, failing if there is none.
+    pub fn anchor_error(&self) -> Result<AnchorErrorInfo> {
+        self.error
+            .anchor_error()
+            .ok_or_else(|| anyhow!("Expected an Anchor error, but nothing was found in the logs"))
+    }
+
+    /// Asserts that the Anchor error was caused by the account with the given name, as it
+    /// is named in the instruction's accounts struct.
+    pub fn caused_by_account(&self, account_name: &str) -> Result<&Self> {
+        let anchor_error = self.anchor_error()?;
+        match &anchor_error.account {
+            Some(account) if account == account_name => Ok(self),
+            Some(account) => Err(anyhow!(
+                "Expected Anchor error {} to be caused by account '{}', but it was caused by '{}'",
+                anchor_error.error_name,
+                account_name,
+                account
+            )),
+            None => Err(anyhow!(
+                "Expected Anchor error {} to be caused by account '{}', but no account was logged",
+                anchor_error.error_name,
+                account_name
+            )),
+        }
+    }
+
+    /// Asserts that the failed constraint compared the given left and right values.
+    ///
+    /// Public keys and other values are compared by their string representations.
+    pub fn with_constraint_values(
+        &self,
+        left: impl ToString,
+        right: impl ToString,
+    ) -> Result<&Self> {
+        let anchor_error = self.anchor_error()?;
+        let compared_values = anchor_error.compared_values.as_ref().ok_or_else(|| {
+            anyhow!(
+                "Expected Anchor error {} to log compared values, but none were logged",
+                anchor_error.error_name
+            )
+        })?;
+        let expected = (left.to_string(), right.to_string());
+        if compared_values.to_strings() == expected {
+            return Ok(self);
+        }
+        let address_book = &self.error.address_book;
+        let (actual_left, actual_right) = compared_values.format(address_book);
+        Err(anyhow!(
+            "Expected Anchor error {} to compare {} with {}, but it compared {} with {}",
+            anchor_error.error_name,
+            address_book.replace_addresses_with_labels(&expected.0),
+            address_book.replace_addresses_with_labels(&expected.1),
+            actual_left,
+            actual_right
+        ))
+    }
+
+    /// Returns the program invocations of the failed transaction.
+    pub fn invocation_tree(&self) -> InvocationTree {
+        self.error.invocation_tree()
+    }
+
     /// Returns the underlying transaction error for custom assertions.
     pub fn error(&self) -> &TXError {
         &self.error
@@ -160,19 +329,44 @@ impl TXErrorAssertions {
 
 /// Assertions for successful transactions.
 pub struct TXSuccessAssertions {
-    /// The successful transaction metadata
-    pub metadata: TransactionMetadata,
+    /// The successful transaction
+    pub success: TXSuccess,
 }
 
 impl TXSuccessAssertions {
     /// Returns the compute units consumed by the transaction.
     pub fn compute_units(&self) -> u64 {
-        self.metadata.compute_units_consumed
+        self.success.compute_units_consumed
     }
 
     /// Returns the transaction logs.
     pub fn logs(&self) -> &Vec<String> {
-        &self.metadata.logs
+        &self.success.logs
+    }
+
+    /// Returns the program invocations of the transaction.
+    pub fn invocation_tree(&self) -> InvocationTree {
+        self.success.invocation_tree()
+    }
+
+    /// Returns the SOL and token balances of every account, before and after the transaction.
+    pub fn balances(&self) -> &BalanceReport {
+        &self.success.balances
+    }
+
+    /// Returns the changes to every writable account of the transaction.
+    pub fn account_diffs(&self) -> &[AccountDiff] {
+        &self.success.account_diffs
+    }
+
+    /// Returns the changes to the given account, failing if it was not writable in the transaction.
+    pub fn account_diff(&self, pubkey: &Pubkey) -> Result<&AccountDiff> {
+        self.success.account_diff(pubkey).ok_or_else(|| {
+            anyhow!(
+                "Account {} was not writable in the transaction",
+                self.success.address_book.get_label(pubkey)
+            )
+        })
     }
 }
 
@@ -279,6 +473,10 @@ pub trait TXResultAssertions {
     ///
     /// // Can access transaction metadata
     /// println!("Used {} compute units", assertions.compute_units());
+    ///
+    /// // ...and the changes made to each writable account
+    /// assertions.account_diff(&owner_ata.key)?;
+    /// assertions.success.print_account_diffs();
     /// # Ok(())
     /// # }
     /// ```
@@ -295,7 +493,7 @@ impl TXResultAssertions for TXResult {
 
     fn succeeds(self) -> Result<TXSuccessAssertions> {
         match self {
-            Result::Ok(metadata) => Ok(TXSuccessAssertions { metadata }),
+            Result::Ok(success) => Ok(TXSuccessAssertions { success }),
             Result::Err(e) => {
                 e.print_error();
                 e.address_book.print_all();
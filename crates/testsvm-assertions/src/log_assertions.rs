@@ -0,0 +1,134 @@
+//! # Log Assertions
+//!
+//! Assertions on the log lines of a transaction, whether it succeeded or failed.
+//!
+//! Every log line is matched in both its raw form and its labeled form, where addresses are
+//! replaced by their labels from the [AddressBook]. A line such as
+//! `Program log: authority: 9xQe...` can therefore be matched as `authority: alice`.
+//! When an assertion fails, the program invocations of the transaction are printed along
+//! with their labeled logs.
+
+use anyhow::*;
+use colored::Colorize;
+use regex::Regex;
+use testsvm_core::prelude::*;
+
+use crate::{TXErrorAssertions, TXSuccessAssertions};
+
+/// Assertions on the logs of a transaction.
+///
+/// Implemented by [TXSuccessAssertions] and [TXErrorAssertions].
+pub trait LogAssertions {
+    /// Returns the log lines of the transaction.
+    fn transaction_logs(&self) -> &[String];
+
+    /// Returns the address book used to label the logs.
+    fn log_address_book(&self) -> &AddressBook;
+
+    /// Asserts that a log line contains `text`.
+    fn log_contains(&self, text: &str) -> Result<&Self> {
+        let address_book = self.log_address_book();
+        if self
+            .transaction_logs()
+            .iter()
+            .any(|line| line_contains(address_book, line, text))
+        {
+            return Ok(self);
+        }
+        print_logs(self.transaction_logs(), address_book);
+        bail!("Expected a log line containing '{text}'")
+    }
+
+    /// Asserts that a log line matches the regular expression `pattern`.
+    fn log_matches(&self, pattern: &str) -> Result<&Self> {
+        let regex =
+            Regex::new(pattern).with_context(|| format!("Invalid log pattern '{pattern}'"))?;
+        let address_book = self.log_address_book();
+        if self.transaction_logs().iter().any(|line| {
+            regex.is_match(line)
+                || regex.is_match(&address_book.replace_addresses_with_labels(line))
+        }) {
+            return Ok(self);
+        }
+        print_logs(self.transaction_logs(), address_book);
+        bail!("Expected a log line matching '{pattern}'")
+    }
+
+    /// Asserts that an invocation of `program_id` at the given CPI depth emitted a log line
+    /// containing `text`.
+    ///
+    /// Depth 1 is a top-level instruction, depth 2 a CPI made by it, and so on.
+    fn log_emitted_by(&self, program_id: &Pubkey, depth: usize, text: &str) -> Result<&Self> {
+        let address_book = self.log_address_book();
+        let tree = InvocationTree::parse(self.transaction_logs());
+        if tree
+            .invocations_of(program_id)
+            .filter(|invocation| invocation.depth == depth)
+            .any(|invocation| {
+                invocation
+                    .logs
+                    .iter()
+                    .any(|line| line_contains(address_book, line, text))
+            })
+        {
+            return Ok(self);
+        }
+        print_logs(self.transaction_logs(), address_book);
+        bail!(
+            "Expected {} at depth {depth} to log a line containing '{text}'",
+            address_book.get_label(program_id)
+        )
+    }
+
+    /// Asserts that no log line contains `text`.
+    fn log_absent(&self, text: &str) -> Result<&Self> {
+        let address_book = self.log_address_book();
+        if let Some(line) = self
+            .transaction_logs()
+            .iter()
+            .find(|line| line_contains(address_book, line, text))
+        {
+            print_logs(self.transaction_logs(), address_book);
+            bail!(
+                "Expected no log line containing '{text}', but found '{}'",
+                address_book.replace_addresses_with_labels(line)
+            );
+        }
+        Ok(self)
+    }
+}
+
+impl LogAssertions for TXSuccessAssertions {
+    fn transaction_logs(&self) -> &[String] {
+        &self.success.logs
+    }
+
+    fn log_address_book(&self) -> &AddressBook {
+        &self.success.address_book
+    }
+}
+
+impl LogAssertions for TXErrorAssertions {
+    fn transaction_logs(&self) -> &[String] {
+        &self.error.metadata.meta.logs
+    }
+
+    fn log_address_book(&self) -> &AddressBook {
+        &self.error.address_book
+    }
+}
+
+/// Returns whether a log line contains `text` in its raw or labeled form.
+fn line_contains(address_book: &AddressBook, line: &str, text: &str) -> bool {
+    line.contains(text) || address_book.replace_addresses_with_labels(line).contains(text)
+}
+
+/// Prints the program invocations of a transaction along with their labeled logs.
+fn print_logs(logs: &[String], address_book: &AddressBook) {
+    println!(
+        "\n{} {}",
+        "📜".yellow(),
+        "Program Invocations:".yellow().bold()
+    );
+    InvocationTree::parse(logs).print(address_book);
+}
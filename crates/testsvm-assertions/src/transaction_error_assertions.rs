@@ -0,0 +1,275 @@
+//! # Transaction Error Assertions
+//!
+//! Assertions for transactions that failed outside of any instruction.
+//!
+//! Many failures are reported by the runtime before or around instruction execution, as a
+//! [TransactionError] other than [TransactionError::InstructionError]: a missing or
+//! underfunded fee payer, an account left below the rent-exempt minimum, a stale blockhash,
+//! and so on. These assertions match those failures, resolving account indices to labels
+//! from the [AddressBook] where the error carries one.
+
+use anyhow::*;
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+use testsvm_core::prelude::*;
+
+use crate::TXErrorAssertions;
+
+impl TXErrorAssertions {
+    /// Asserts that the transaction error satisfies `matcher`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// result.fails()?.with_transaction_error(|err| {
+    ///     matches!(err, TransactionError::InsufficientFundsForRent { .. })
+    /// })?;
+    /// ```
+    pub fn with_transaction_error(
+        &self,
+        matcher: impl Fn(&TransactionError) -> bool,
+    ) -> Result<&Self> {
+        if matcher(&self.error.metadata.err) {
+            return Ok(self);
+        }
+        Err(anyhow!(
+            "Transaction error did not match, got '{}'",
+            self.describe_error()
+        ))
+    }
+
+    /// Asserts that the transaction failed because an account it references does not exist,
+    /// e.g. an unfunded fee payer.
+    pub fn with_account_not_found(&self) -> Result<&Self> {
+        self.expect_error("AccountNotFound", |err| {
+            matches!(err, TransactionError::AccountNotFound)
+        })
+    }
+
+    /// Asserts that the transaction failed because the fee payer cannot pay the fee.
+    pub fn with_insufficient_funds_for_fee(&self) -> Result<&Self> {
+        self.expect_error("InsufficientFundsForFee", |err| {
+            matches!(err, TransactionError::InsufficientFundsForFee)
+        })
+    }
+
+    /// Asserts that the transaction failed because it would leave `pubkey` with lamports
+    /// below the rent-exempt minimum.
+    pub fn with_insufficient_funds_for_rent(&self, pubkey: &Pubkey) -> Result<&Self> {
+        let label = self.error.address_book.get_label(pubkey);
+        self.expect_error(&format!("InsufficientFundsForRent for {label}"), |err| {
+            matches!(
+                err,
+                TransactionError::InsufficientFundsForRent { account_index }
+                    if self.account_at(*account_index as usize) == Some(*pubkey)
+            )
+        })
+    }
+
+    /// Asserts that the transaction failed because it was already processed.
+    pub fn with_already_processed(&self) -> Result<&Self> {
+        self.expect_error("AlreadyProcessed", |err| {
+            matches!(err, TransactionError::AlreadyProcessed)
+        })
+    }
+
+    /// Asserts that the transaction failed because its blockhash is unknown or expired.
+    pub fn with_blockhash_not_found(&self) -> Result<&Self> {
+        self.expect_error("BlockhashNotFound", |err| {
+            matches!(err, TransactionError::BlockhashNotFound)
+        })
+    }
+
+    /// Asserts that the transaction failed because it contains a duplicate instruction.
+    pub fn with_duplicate_instruction(&self) -> Result<&Self> {
+        self.expect_error("DuplicateInstruction", |err| {
+            matches!(err, TransactionError::DuplicateInstruction(_))
+        })
+    }
+
+    /// Asserts that the transaction failed because it invokes a program that does not exist.
+    pub fn with_program_account_not_found(&self) -> Result<&Self> {
+        self.expect_error("ProgramAccountNotFound", |err| {
+            matches!(err, TransactionError::ProgramAccountNotFound)
+        })
+    }
+
+    /// Asserts that the transaction failed because the fee payer did not sign it.
+    pub fn with_missing_signature_for_fee(&self) -> Result<&Self> {
+        self.expect_error("MissingSignatureForFee", |err| {
+            matches!(err, TransactionError::MissingSignatureForFee)
+        })
+    }
+
+    /// Asserts that the transaction failed because a signature could not be verified.
+    pub fn with_signature_failure(&self) -> Result<&Self> {
+        self.expect_error("SignatureFailure", |err| {
+            matches!(err, TransactionError::SignatureFailure)
+        })
+    }
+
+    /// Asserts that the transaction failed because the fee payer cannot pay fees, e.g.
+    /// because it is not a system account.
+    pub fn with_invalid_account_for_fee(&self) -> Result<&Self> {
+        self.expect_error("InvalidAccountForFee", |err| {
+            matches!(err, TransactionError::InvalidAccountForFee)
+        })
+    }
+
+    /// Asserts that the transaction failed because an instruction exceeded its compute budget.
+    ///
+    /// BPF programs that run out of compute units fail with
+    /// [InstructionError::ProgramFailedToComplete] rather than
+    /// [InstructionError::ComputationalBudgetExceeded], so that error is matched as well when
+    /// the logs show the compute meter was exceeded.
+    pub fn with_compute_budget_exceeded(&self) -> Result<&Self> {
+        let exceeded_meter = self
+            .error
+            .metadata
+            .meta
+            .logs
+            .iter()
+            .any(|log| log.contains("exceeded CUs meter"));
+        self.expect_error("ComputationalBudgetExceeded", |err| match err {
+            TransactionError::InstructionError(
+                _,
+                InstructionError::ComputationalBudgetExceeded,
+            ) => true,
+            TransactionError::InstructionError(_, InstructionError::ProgramFailedToComplete) => {
+                exceeded_meter
+            }
+            _ => false,
+        })
+    }
+
+    /// Asserts that the transaction error matches `matcher`, describing it as `expected` otherwise.
+    fn expect_error(
+        &self,
+        expected: &str,
+        matcher: impl Fn(&TransactionError) -> bool,
+    ) -> Result<&Self> {
+        if matcher(&self.error.metadata.err) {
+            return Ok(self);
+        }
+        Err(anyhow!(
+            "Expected transaction error {}, but got '{}'",
+            expected,
+            self.describe_error()
+        ))
+    }
+
+    /// Returns the account at the given index of the transaction.
+    fn account_at(&self, index: usize) -> Option<Pubkey> {
+        self.error.account_keys().get(index).copied()
+    }
+
+    /// Describes the transaction error, resolving account indices to labels.
+    fn describe_error(&self) -> String {
+        match &self.error.metadata.err {
+            TransactionError::InsufficientFundsForRent { account_index } => {
+                match self.account_at(*account_index as usize) {
+                    Some(pubkey) => format!(
+                        "InsufficientFundsForRent for {}",
+                        self.error.address_book.get_label(&pubkey)
+                    ),
+                    None => format!("InsufficientFundsForRent for account {account_index}"),
+                }
+            }
+            err => format!("{err:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{compute_budget::ComputeBudgetInstruction, system_instruction};
+
+    use super::*;
+    use crate::TXResultAssertions;
+
+    #[test]
+    fn test_fee_payer_errors() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+
+        // A fee payer without an account cannot pay for anything
+        let unfunded = Keypair::new();
+        let transfer_ix = system_instruction::transfer(&unfunded.pubkey(), &alice.pubkey(), 1);
+        let transaction = Transaction::new_signed_with_payer(
+            &[transfer_ix],
+            Some(&unfunded.pubkey()),
+            &[&unfunded],
+            env.svm.latest_blockhash(),
+        );
+        env.execute_transaction(transaction)
+            .fails()?
+            .with_account_not_found()?
+            .with_transaction_error(|err| matches!(err, TransactionError::AccountNotFound))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insufficient_funds_for_rent() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let data_account = env.new_keypair("data_account");
+
+        // A new account with data funded below the rent-exempt minimum is rejected
+        let create_ix = system_instruction::create_account(
+            &alice.pubkey(),
+            &data_account.pubkey(),
+            1,
+            100,
+            &system_program::ID,
+        );
+        let assertions = env
+            .execute_ixs_with_signers(&[create_ix], &[&alice, &data_account])
+            .fails()?;
+        assertions.with_insufficient_funds_for_rent(&data_account.pubkey())?;
+        assert!(
+            assertions
+                .with_insufficient_funds_for_rent(&alice.pubkey())
+                .is_err()
+        );
+        assert!(assertions.with_insufficient_funds_for_fee().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_already_processed() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let bob = env.new_wallet("bob")?;
+
+        // The same transaction cannot be processed twice
+        let transfer_ix = system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), 1_000);
+        let transaction = Transaction::new_signed_with_payer(
+            &[transfer_ix],
+            Some(&env.default_fee_payer()),
+            &[&env.default_fee_payer, &alice],
+            env.svm.latest_blockhash(),
+        );
+        env.execute_transaction(transaction.clone()).succeeds()?;
+        env.execute_transaction(transaction)
+            .fails()?
+            .with_already_processed()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_budget_exceeded() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let bob = env.new_wallet("bob")?;
+
+        let limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(1);
+        let transfer_ix = system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), 1_000);
+        env.execute_ixs_with_signers(&[limit_ix, transfer_ix], &[&alice])
+            .fails()?
+            .with_compute_budget_exceeded()?;
+
+        Ok(())
+    }
+}
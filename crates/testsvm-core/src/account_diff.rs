@@ -0,0 +1,264 @@
+//! # Account Diffs
+//!
+//! Before/after comparisons of the accounts written by a transaction.
+//!
+//! Every successful transaction executed through a [crate::TestSVM] captures the state of
+//! its writable accounts before and after execution. An [AccountDiff] describes what
+//! changed in one of those accounts:
+//!
+//! - **Lamports**: Balance changes, including transaction fees and rent
+//! - **Owner**: Assignment to a different program
+//! - **Data Length**: Reallocations
+//! - **Lifecycle**: Account creation and closure
+//! - **Data**: Byte ranges of the account data that changed
+
+use std::ops::Range;
+
+use colored::Colorize;
+use solana_address_book::AddressBook;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+/// Maximum length of a changed byte range for which the bytes themselves are printed.
+const MAX_PRINTED_RANGE_LEN: usize = 32;
+
+/// The change to a single writable account made by a transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountDiff {
+    /// Address of the account
+    pub pubkey: Pubkey,
+    /// State of the account before the transaction, if it existed
+    pub pre: Option<Account>,
+    /// State of the account after the transaction, if it still exists
+    pub post: Option<Account>,
+}
+
+impl AccountDiff {
+    /// Creates a diff between two states of an account.
+    ///
+    /// Accounts without any lamports are treated as nonexistent.
+    pub fn new(pubkey: Pubkey, pre: Option<Account>, post: Option<Account>) -> Self {
+        Self {
+            pubkey,
+            pre: pre.filter(|account| account.lamports > 0),
+            post: post.filter(|account| account.lamports > 0),
+        }
+    }
+
+    /// Returns whether the account was created by the transaction.
+    pub fn is_created(&self) -> bool {
+        self.pre.is_none() && self.post.is_some()
+    }
+
+    /// Returns whether the account was closed by the transaction.
+    pub fn is_closed(&self) -> bool {
+        self.pre.is_some() && self.post.is_none()
+    }
+
+    /// Returns whether anything about the account changed.
+    pub fn is_changed(&self) -> bool {
+        self.pre != self.post
+    }
+
+    /// Returns the lamport balances before and after the transaction.
+    pub fn lamports(&self) -> (u64, u64) {
+        (
+            self.pre.as_ref().map_or(0, |account| account.lamports),
+            self.post.as_ref().map_or(0, |account| account.lamports),
+        )
+    }
+
+    /// Returns the change in lamports, which is negative if the balance decreased.
+    pub fn lamports_change(&self) -> i128 {
+        let (pre, post) = self.lamports();
+        post as i128 - pre as i128
+    }
+
+    /// Returns the previous and new owner if the owner changed.
+    ///
+    /// Creation and closure are not reported as owner changes.
+    pub fn owner_change(&self) -> Option<(Pubkey, Pubkey)> {
+        match (&self.pre, &self.post) {
+            (Some(pre), Some(post)) if pre.owner != post.owner => Some((pre.owner, post.owner)),
+            _ => None,
+        }
+    }
+
+    /// Returns the data lengths before and after the transaction if they differ.
+    pub fn data_len_change(&self) -> Option<(usize, usize)> {
+        let (pre, post) = (self.pre_data().len(), self.post_data().len());
+        (pre != post).then_some((pre, post))
+    }
+
+    /// Returns the byte ranges of the account data that changed.
+    ///
+    /// Bytes that only exist before or after a reallocation are considered changed.
+    pub fn data_changes(&self) -> Vec<Range<usize>> {
+        let (pre, post) = (self.pre_data(), self.post_data());
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for i in 0..pre.len().max(post.len()) {
+            if pre.get(i) == post.get(i) {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(range) if range.end == i => range.end = i + 1,
+                _ => ranges.push(i..i + 1),
+            }
+        }
+        ranges
+    }
+
+    /// Print the diff, formatted using an [AddressBook].
+    pub fn print(&self, address_book: &AddressBook) {
+        let status = if self.is_created() {
+            " (created)".green().to_string()
+        } else if self.is_closed() {
+            " (closed)".red().to_string()
+        } else {
+            String::new()
+        };
+        println!("   {}{}", address_book.format_address(&self.pubkey), status);
+
+        let (pre_lamports, post_lamports) = self.lamports();
+        if pre_lamports != post_lamports {
+            let change = self.lamports_change();
+            let change = if change > 0 {
+                format!("+{change}").green()
+            } else {
+                change.to_string().red()
+            };
+            println!(
+                "     {} {} → {} ({})",
+                "lamports:".dimmed(),
+                pre_lamports,
+                post_lamports,
+                change
+            );
+        }
+        if let Some((pre_owner, post_owner)) = self.owner_change() {
+            println!(
+                "     {} {} → {}",
+                "owner:".dimmed(),
+                address_book.format_address(&pre_owner),
+                address_book.format_address(&post_owner)
+            );
+        }
+        if let Some((pre_len, post_len)) = self.data_len_change() {
+            println!("     {} {} → {}", "data len:".dimmed(), pre_len, post_len);
+        }
+        for range in self.data_changes() {
+            let hex = |data: &[u8]| {
+                data[range.start.min(data.len())..range.end.min(data.len())]
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<String>()
+            };
+            if range.len() <= MAX_PRINTED_RANGE_LEN {
+                println!(
+                    "     {} [{}..{}] {} → {}",
+                    "data".dimmed(),
+                    range.start,
+                    range.end,
+                    hex(self.pre_data()).red(),
+                    hex(self.post_data()).green()
+                );
+            } else {
+                println!(
+                    "     {} [{}..{}] {} bytes changed",
+                    "data".dimmed(),
+                    range.start,
+                    range.end,
+                    range.len()
+                );
+            }
+        }
+    }
+
+    fn pre_data(&self) -> &[u8] {
+        self.pre.as_ref().map_or(&[], |account| &account.data)
+    }
+
+    fn post_data(&self) -> &[u8] {
+        self.post.as_ref().map_or(&[], |account| &account.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestSVM;
+    use solana_sdk::{signature::Signer, system_instruction, system_program};
+
+    fn account(lamports: u64, data: &[u8]) -> Account {
+        Account {
+            lamports,
+            data: data.to_vec(),
+            owner: system_program::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn test_data_changes_are_grouped_into_ranges() {
+        let diff = AccountDiff::new(
+            Pubkey::new_unique(),
+            Some(account(10, &[0, 1, 2, 3, 4, 5])),
+            Some(account(10, &[0, 9, 9, 3, 4, 9, 9])),
+        );
+        assert_eq!(diff.data_changes(), vec![1..3, 5..7]);
+        assert_eq!(diff.data_len_change(), Some((6, 7)));
+        assert_eq!(diff.lamports_change(), 0);
+        assert!(diff.owner_change().is_none());
+    }
+
+    #[test]
+    fn test_created_and_closed_accounts() {
+        let pubkey = Pubkey::new_unique();
+        let created = AccountDiff::new(pubkey, None, Some(account(5, &[1])));
+        assert!(created.is_created());
+        assert_eq!(created.lamports_change(), 5);
+
+        // Accounts drained of lamports are closed, even if LiteSVM still returns them
+        let closed = AccountDiff::new(pubkey, Some(account(5, &[1])), Some(account(0, &[])));
+        assert!(closed.is_closed());
+        assert_eq!(closed.lamports_change(), -5);
+    }
+
+    #[test]
+    fn test_transaction_returns_diffs_for_writable_accounts() -> anyhow::Result<()> {
+        let mut env = TestSVM::builder().print_account_diffs(true).build()?;
+        let alice = env.new_wallet("alice")?;
+        let recipient = Pubkey::new_unique();
+
+        let success = env.execute_ixs_with_signers(
+            &[system_instruction::transfer(
+                &alice.pubkey(),
+                &recipient,
+                1_000_000,
+            )],
+            &[&alice],
+        )?;
+
+        let recipient_diff = success.account_diff(&recipient).unwrap();
+        assert!(recipient_diff.is_created());
+        assert_eq!(recipient_diff.lamports_change(), 1_000_000);
+        assert_eq!(
+            success
+                .account_diff(&alice.pubkey())
+                .unwrap()
+                .lamports_change(),
+            -1_000_000
+        );
+        // The fee payer pays the transaction fee
+        assert!(
+            success
+                .account_diff(&env.default_fee_payer())
+                .unwrap()
+                .lamports_change()
+                < 0
+        );
+        // Read-only accounts are not diffed
+        assert!(success.account_diff(&system_program::ID).is_none());
+        Ok(())
+    }
+}
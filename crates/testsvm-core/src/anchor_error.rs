@@ -0,0 +1,235 @@
+//! # Anchor Errors
+//!
+//! Structured Anchor errors parsed from transaction logs.
+//!
+//! When an Anchor program fails, it logs a single line describing the error, such as
+//! `AnchorError caused by account: miner. Error Code: ConstraintHasOne. Error Number: 2001. ...`,
+//! followed by the compared values for constraint violations. An [AnchorErrorInfo] captures
+//! every part of that output so that tests can assert on it directly.
+
+use std::str::FromStr;
+
+use colored::Colorize;
+use solana_address_book::AddressBook;
+use solana_sdk::pubkey::Pubkey;
+
+/// The values compared by a failed constraint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ComparedValues {
+    /// Two public keys, e.g. from a `has_one` constraint
+    Pubkeys {
+        /// The value found in the account
+        left: Pubkey,
+        /// The value it was expected to equal
+        right: Pubkey,
+    },
+    /// Two other values, logged as strings
+    Values {
+        /// The value found in the account
+        left: String,
+        /// The value it was expected to equal
+        right: String,
+    },
+}
+
+impl ComparedValues {
+    /// Returns the left and right values as strings.
+    pub fn to_strings(&self) -> (String, String) {
+        match self {
+            ComparedValues::Pubkeys { left, right } => (left.to_string(), right.to_string()),
+            ComparedValues::Values { left, right } => (left.clone(), right.clone()),
+        }
+    }
+
+    /// Formats the values, labeling public keys with an [AddressBook].
+    pub fn format(&self, address_book: &AddressBook) -> (String, String) {
+        match self {
+            ComparedValues::Pubkeys { left, right } => (
+                address_book.format_address(left),
+                address_book.format_address(right),
+            ),
+            ComparedValues::Values { left, right } => (left.clone(), right.clone()),
+        }
+    }
+}
+
+/// An error logged by an Anchor program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnchorErrorInfo {
+    /// Name of the error, e.g. `ConstraintHasOne`
+    pub error_name: String,
+    /// Error number, including the error code offset for program-defined errors
+    pub error_number: u32,
+    /// Error message
+    pub error_message: String,
+    /// Source file the error was thrown in, if logged
+    pub file: Option<String>,
+    /// Source line the error was thrown at, if logged
+    pub line: Option<u32>,
+    /// Name of the account that caused the error, if logged
+    pub account: Option<String>,
+    /// The values compared by a failed constraint, if logged
+    pub compared_values: Option<ComparedValues>,
+}
+
+impl AnchorErrorInfo {
+    /// Parses the last Anchor error from transaction logs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use testsvm_core::AnchorErrorInfo;
+    ///
+    /// let logs = [
+    ///     "Program log: AnchorError caused by account: miner. Error Code: AccountNotInitialized. Error Number: 3012. Error Message: The program expected this account to be already initialized.",
+    /// ]
+    /// .map(String::from);
+    ///
+    /// let error = AnchorErrorInfo::parse(&logs).unwrap();
+    /// assert_eq!(error.error_name, "AccountNotInitialized");
+    /// assert_eq!(error.error_number, 3012);
+    /// assert_eq!(error.account.as_deref(), Some("miner"));
+    /// ```
+    pub fn parse(logs: &[String]) -> Option<Self> {
+        let messages: Vec<&str> = logs
+            .iter()
+            .map(|line| line.strip_prefix("Program log: ").unwrap_or(line))
+            .collect();
+        let index = messages
+            .iter()
+            .rposition(|message| message.starts_with("AnchorError "))?;
+
+        let (origin, rest) = messages[index].split_once(". Error Code: ")?;
+        let (error_name, rest) = rest.split_once(". Error Number: ")?;
+        let (error_number, error_message) = rest.split_once(". Error Message: ")?;
+        let mut info = Self {
+            error_name: error_name.to_string(),
+            error_number: error_number.parse().ok()?,
+            error_message: error_message
+                .strip_suffix('.')
+                .unwrap_or(error_message)
+                .to_string(),
+            file: None,
+            line: None,
+            account: None,
+            compared_values: parse_compared_values(&messages[index + 1..]),
+        };
+
+        if let Some(account) = origin.strip_prefix("AnchorError caused by account: ") {
+            info.account = Some(account.to_string());
+        } else if let Some(source) = origin.strip_prefix("AnchorError thrown in ") {
+            let (file, line) = source.rsplit_once(':')?;
+            info.file = Some(file.to_string());
+            info.line = line.parse().ok();
+        }
+        Some(info)
+    }
+
+    /// Print the error, formatted using an [AddressBook].
+    pub fn print(&self, address_book: &AddressBook) {
+        println!(
+            "\n{} {} ({})",
+            "⚓".red(),
+            self.error_name.red().bold(),
+            self.error_number
+        );
+        println!("   {}", self.error_message);
+        if let Some(account) = &self.account {
+            println!("   {} {}", "Account:".dimmed(), account.bright_yellow());
+        }
+        if let Some(file) = &self.file {
+            let line = self.line.map(|line| format!(":{line}")).unwrap_or_default();
+            println!("   {} {file}{line}", "Thrown in:".dimmed());
+        }
+        if let Some(compared_values) = &self.compared_values {
+            let (left, right) = compared_values.format(address_book);
+            println!("   {} {left}", "Left:".dimmed());
+            println!("   {} {right}", "Right:".dimmed());
+        }
+    }
+}
+
+/// Parses the compared values logged after an Anchor error.
+///
+/// Public keys are logged as `Left:`, `<pubkey>`, `Right:`, `<pubkey>` on separate lines,
+/// and other values as `Left: <value>` and `Right: <value>`.
+fn parse_compared_values(messages: &[&str]) -> Option<ComparedValues> {
+    match messages {
+        ["Left:", left, "Right:", right, ..] => Some(ComparedValues::Pubkeys {
+            left: Pubkey::from_str(left).ok()?,
+            right: Pubkey::from_str(right).ok()?,
+        }),
+        [left, right, ..] => Some(ComparedValues::Values {
+            left: left.strip_prefix("Left: ")?.to_string(),
+            right: right.strip_prefix("Right: ")?.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_constraint_pubkeys() {
+        let left = Pubkey::new_unique();
+        let right = Pubkey::new_unique();
+        let logs = [
+            "Program QMNeHCGYnLVDn1icRAfQZpjPLBNkfGbSKRB83G5d8KB invoke [1]".to_string(),
+            "Program log: Instruction: StakeTokens".to_string(),
+            "Program log: AnchorError caused by account: miner. Error Code: ConstraintHasOne. Error Number: 2001. Error Message: A has one constraint was violated.".to_string(),
+            "Program log: Left:".to_string(),
+            format!("Program log: {left}"),
+            "Program log: Right:".to_string(),
+            format!("Program log: {right}"),
+        ];
+
+        let error = AnchorErrorInfo::parse(&logs).unwrap();
+        assert_eq!(error.error_name, "ConstraintHasOne");
+        assert_eq!(error.error_number, 2001);
+        assert_eq!(error.error_message, "A has one constraint was violated");
+        assert_eq!(error.account.as_deref(), Some("miner"));
+        assert_eq!(error.file, None);
+        assert_eq!(
+            error.compared_values,
+            Some(ComparedValues::Pubkeys { left, right })
+        );
+    }
+
+    #[test]
+    fn test_parse_thrown_error_with_values() {
+        let logs = [
+            "Program log: AnchorError thrown in programs/quarry-mine/src/lib.rs:42. Error Code: InsufficientBalance. Error Number: 6003. Error Message: Insufficient staked balance. Withdraw less.".to_string(),
+            "Program log: Left: 200".to_string(),
+            "Program log: Right: 100".to_string(),
+        ];
+
+        let error = AnchorErrorInfo::parse(&logs).unwrap();
+        assert_eq!(error.error_name, "InsufficientBalance");
+        assert_eq!(error.error_number, 6003);
+        assert_eq!(
+            error.error_message,
+            "Insufficient staked balance. Withdraw less"
+        );
+        assert_eq!(
+            error.file.as_deref(),
+            Some("programs/quarry-mine/src/lib.rs")
+        );
+        assert_eq!(error.line, Some(42));
+        assert_eq!(error.account, None);
+        assert_eq!(
+            error.compared_values,
+            Some(ComparedValues::Values {
+                left: "200".to_string(),
+                right: "100".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_without_anchor_error() {
+        let logs = ["Program log: Instruction: StakeTokens".to_string()];
+        assert_eq!(AnchorErrorInfo::parse(&logs), None);
+    }
+}
@@ -0,0 +1,327 @@
+//! # Anchor Workspaces
+//!
+//! Loading of locally built programs from an Anchor workspace.
+//!
+//! Dumped third-party programs are loaded from `fixtures/programs/` with
+//! [TestSVM::add_program_fixture]. Programs built with `anchor build` live in the workspace
+//! instead, as `target/deploy/{name}.so` next to their `{name}-keypair.json`.
+//! [TestSVM::add_anchor_workspace_programs] loads all of them at once, deriving each program
+//! id from its keypair, so that ids and paths do not need to be wired by hand in every test.
+//!
+//! The programs declared in `Anchor.toml` for the provider's cluster are checked against the
+//! build output: a declared program that was not built, or whose address does not match its
+//! keypair, is an error.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::*;
+use serde::Deserialize;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signer, read_keypair_file},
+};
+
+use crate::TestSVM;
+
+/// The parts of an `Anchor.toml` needed to locate its programs.
+#[derive(Debug, Deserialize)]
+struct AnchorToml {
+    #[serde(default)]
+    provider: Option<ProviderConfig>,
+    /// Programs declared for each cluster, keyed by cluster and then program name
+    #[serde(default)]
+    programs: BTreeMap<String, BTreeMap<String, ProgramDeclaration>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderConfig {
+    cluster: String,
+}
+
+/// A program address, declared either as `name = "<ADDRESS>"` or
+/// `name = { address = "<ADDRESS>", ... }`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ProgramDeclaration {
+    Address(String),
+    Detailed { address: String },
+}
+
+impl ProgramDeclaration {
+    fn address(&self) -> &str {
+        match self {
+            ProgramDeclaration::Address(address) => address,
+            ProgramDeclaration::Detailed { address } => address,
+        }
+    }
+}
+
+impl AnchorToml {
+    /// Returns the programs declared for the provider's cluster, which defaults to `localnet`.
+    fn declared_programs(&self) -> Result<BTreeMap<String, Pubkey>> {
+        let cluster = self
+            .provider
+            .as_ref()
+            .map_or("localnet", |provider| programs_cluster(&provider.cluster));
+        self.programs
+            .get(cluster)
+            .into_iter()
+            .flatten()
+            .map(|(name, declaration)| {
+                let address = declaration.address();
+                let program_id = address.parse().with_context(|| {
+                    format!("Invalid address '{address}' for program '{name}'")
+                })?;
+                Ok((name.clone(), program_id))
+            })
+            .collect()
+    }
+}
+
+/// Returns the `[programs.<cluster>]` section name of a provider cluster.
+///
+/// As in Anchor, clusters are matched case-insensitively by name or moniker, e.g. `Devnet` or
+/// `d`, and well-known RPC URLs are mapped to their cluster. Any other cluster, such as a
+/// custom RPC URL, falls back to `localnet`.
+fn programs_cluster(cluster: &str) -> &'static str {
+    let cluster = cluster.trim().to_lowercase();
+    let host = match cluster.split_once("://") {
+        Some((_, rest)) => rest.split(['/', ':']).next().unwrap_or(rest),
+        None => &cluster,
+    };
+    match host {
+        "m" | "mainnet" | "mainnet-beta" | "api.mainnet-beta.solana.com" => "mainnet",
+        "d" | "devnet" | "api.devnet.solana.com" => "devnet",
+        "t" | "testnet" | "api.testnet.solana.com" => "testnet",
+        "g" | "debug" => "debug",
+        _ => "localnet",
+    }
+}
+
+/// Parses the contents of an `Anchor.toml`.
+fn parse_anchor_toml(contents: &str) -> Result<AnchorToml> {
+    toml::from_str(contents).context("Failed to parse Anchor.toml")
+}
+
+impl TestSVM {
+    /// Loads every program built in an Anchor workspace and adds it to the address book.
+    ///
+    /// `path` is the workspace root containing `Anchor.toml`. Each `target/deploy/{name}.so`
+    /// is loaded at the address of `target/deploy/{name}-keypair.json` and labeled `{name}`.
+    /// Returns the ids of the loaded programs, sorted by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a program declared in `Anchor.toml` has not been built, or if its
+    /// declared address does not match its keypair. Run `anchor build` or `anchor keys sync`
+    /// respectively to fix these.
+    pub fn add_anchor_workspace_programs(&mut self, path: impl AsRef<Path>) -> Result<Vec<Pubkey>> {
+        let path = path.as_ref();
+        let anchor_toml_path = path.join("Anchor.toml");
+        let contents = fs::read_to_string(&anchor_toml_path)
+            .with_context(|| format!("Failed to read {}", anchor_toml_path.display()))?;
+        let mut declared_programs = parse_anchor_toml(&contents)
+            .with_context(|| format!("Invalid {}", anchor_toml_path.display()))?
+            .declared_programs()?;
+
+        let deploy_dir = path.join("target").join("deploy");
+        let mut paths = fs::read_dir(&deploy_dir)
+            .with_context(|| format!("Failed to read {}", deploy_dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "so"));
+        paths.sort();
+
+        let mut program_ids = Vec::with_capacity(paths.len());
+        for program_path in &paths {
+            let name = program_path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| anyhow!("Invalid program file name {}", program_path.display()))?;
+            let keypair_path = deploy_dir.join(format!("{name}-keypair.json"));
+            let program_id = read_keypair_file(&keypair_path)
+                .map_err(|e| {
+                    anyhow!(
+                        "Failed to read program keypair {}: {e}",
+                        keypair_path.display()
+                    )
+                })?
+                .pubkey();
+
+            if let Some(declared_id) = declared_programs.remove(name)
+                && declared_id != program_id
+            {
+                bail!(
+                    "Program '{name}' is declared as {declared_id} in Anchor.toml, but its keypair is {program_id}. Run `anchor keys sync`."
+                );
+            }
+
+            self.add_program_from_path(name, program_id, program_path)?;
+            program_ids.push(program_id);
+        }
+
+        if let Some(name) = declared_programs.keys().next() {
+            bail!(
+                "Program '{name}' is declared in Anchor.toml but was not found in {}. Run `anchor build`.",
+                deploy_dir.display()
+            );
+        }
+
+        Ok(program_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::fixtures_dir;
+    use solana_sdk::signature::{Keypair, write_keypair_file};
+    use std::path::PathBuf;
+
+    /// Creates an Anchor workspace in a temporary directory with `quarry_mine` built as `name`.
+    fn create_workspace(name: &str) -> Result<(PathBuf, Keypair)> {
+        let workspace = std::env::temp_dir().join(format!("testsvm-{}", Pubkey::new_unique()));
+        let deploy_dir = workspace.join("target").join("deploy");
+        fs::create_dir_all(&deploy_dir)?;
+        fs::copy(
+            fixtures_dir()?.join("programs").join("quarry_mine.so"),
+            deploy_dir.join(format!("{name}.so")),
+        )?;
+
+        let keypair = Keypair::new();
+        write_keypair_file(&keypair, deploy_dir.join(format!("{name}-keypair.json")))
+            .map_err(|e| anyhow!("Failed to write keypair: {e}"))?;
+        Ok((workspace, keypair))
+    }
+
+    #[test]
+    fn test_parse_anchor_toml() -> Result<()> {
+        let anchor_toml = parse_anchor_toml(
+            r#"
+            [provider]
+            cluster = "devnet"
+            wallet = "~/.config/solana/id.json"
+
+            [programs.localnet]
+            my_program = "11111111111111111111111111111111"
+
+            [programs.devnet]
+            my_program = "So11111111111111111111111111111111111111112"
+            other_program = { address = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", idl = "idl.json" }
+            "#,
+        )?;
+
+        let declared_programs = anchor_toml.declared_programs()?;
+        assert_eq!(declared_programs.len(), 2);
+        assert_eq!(
+            declared_programs["my_program"],
+            anchor_spl::token::spl_token::native_mint::ID
+        );
+        assert_eq!(declared_programs["other_program"], anchor_spl::token::ID);
+        Ok(())
+    }
+
+    #[test]
+    fn test_declared_programs_default_to_localnet() -> Result<()> {
+        let anchor_toml = parse_anchor_toml(
+            r#"
+            [programs.localnet]
+            my_program = "11111111111111111111111111111111"
+            "#,
+        )?;
+
+        let declared_programs = anchor_toml.declared_programs()?;
+        assert_eq!(
+            declared_programs["my_program"],
+            solana_sdk::system_program::ID
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_programs_cluster() {
+        assert_eq!(programs_cluster("Localnet"), "localnet");
+        assert_eq!(programs_cluster("Devnet"), "devnet");
+        assert_eq!(programs_cluster("m"), "mainnet");
+        assert_eq!(programs_cluster("mainnet-beta"), "mainnet");
+        assert_eq!(programs_cluster("https://api.devnet.solana.com"), "devnet");
+        assert_eq!(
+            programs_cluster("https://api.mainnet-beta.solana.com/"),
+            "mainnet"
+        );
+        assert_eq!(programs_cluster("http://127.0.0.1:8899"), "localnet");
+        assert_eq!(programs_cluster("https://rpc.example.com"), "localnet");
+    }
+
+    #[test]
+    fn test_declared_programs_match_cluster_case_insensitively() -> Result<()> {
+        let anchor_toml = parse_anchor_toml(
+            r#"
+            [provider]
+            cluster = "Localnet"
+            wallet = "~/.config/solana/id.json"
+
+            [programs.localnet]
+            my_program = "11111111111111111111111111111111"
+            "#,
+        )?;
+
+        let declared_programs = anchor_toml.declared_programs()?;
+        assert_eq!(
+            declared_programs["my_program"],
+            solana_sdk::system_program::ID
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_anchor_workspace_programs() -> Result<()> {
+        let (workspace, keypair) = create_workspace("my_program")?;
+        fs::write(
+            workspace.join("Anchor.toml"),
+            format!("[programs.localnet]\nmy_program = \"{}\"\n", keypair.pubkey()),
+        )?;
+
+        let mut env = TestSVM::init()?;
+        let program_ids = env.add_anchor_workspace_programs(&workspace)?;
+        assert_eq!(program_ids, vec![keypair.pubkey()]);
+        assert!(env.svm.get_account(&keypair.pubkey()).unwrap().executable);
+        assert_eq!(env.address_book.get_label(&keypair.pubkey()), "my_program");
+
+        fs::remove_dir_all(workspace)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_anchor_workspace_programs_rejects_mismatched_ids() -> Result<()> {
+        let (workspace, _) = create_workspace("my_program")?;
+        fs::write(
+            workspace.join("Anchor.toml"),
+            "[programs.localnet]\nmy_program = \"11111111111111111111111111111111\"\n",
+        )?;
+
+        let mut env = TestSVM::init()?;
+        assert!(env.add_anchor_workspace_programs(&workspace).is_err());
+
+        fs::remove_dir_all(workspace)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_anchor_workspace_programs_rejects_unbuilt_programs() -> Result<()> {
+        let (workspace, keypair) = create_workspace("my_program")?;
+        fs::write(
+            workspace.join("Anchor.toml"),
+            format!(
+                "[programs.localnet]\nmy_program = \"{}\"\nunbuilt_program = \"11111111111111111111111111111111\"\n",
+                keypair.pubkey()
+            ),
+        )?;
+
+        let mut env = TestSVM::init()?;
+        assert!(env.add_anchor_workspace_programs(&workspace).is_err());
+
+        fs::remove_dir_all(workspace)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+//! # Balance Reports
+//!
+//! SOL and SPL token balances of every account in a transaction, before and after execution.
+//!
+//! Balance reports replace the boilerplate of loading each wallet and token account by hand
+//! before and after a transaction. Token amounts are decoded using the decimals of their
+//! mint, and token accounts are labeled by their owner and mint using the [AddressBook].
+//!
+//! Both the SPL Token and Token-2022 programs are supported.
+
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::StateWithExtensions,
+    state::{Account as TokenAccount, Mint},
+};
+use colored::Colorize;
+use litesvm::LiteSVM;
+use solana_address_book::{AddressBook, AddressRole};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+/// The lamport balance of an account before and after a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LamportBalance {
+    /// Address of the account
+    pub pubkey: Pubkey,
+    /// Lamports before the transaction
+    pub pre: u64,
+    /// Lamports after the transaction
+    pub post: u64,
+}
+
+impl LamportBalance {
+    /// Returns the change in lamports, which is negative if the balance decreased.
+    pub fn change(&self) -> i128 {
+        self.post as i128 - self.pre as i128
+    }
+}
+
+/// The balance of an SPL token account before and after a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenBalance {
+    /// Address of the token account
+    pub pubkey: Pubkey,
+    /// Mint of the token account
+    pub mint: Pubkey,
+    /// Owner of the token account
+    pub owner: Pubkey,
+    /// Decimals of the mint, if the mint could be loaded
+    pub decimals: Option<u8>,
+    /// Token amount before the transaction, or `None` if the token account did not exist
+    pub pre: Option<u64>,
+    /// Token amount after the transaction, or `None` if the token account no longer exists
+    pub post: Option<u64>,
+}
+
+impl TokenBalance {
+    /// Returns the change in raw token amount, which is negative if the balance decreased.
+    pub fn change(&self) -> i128 {
+        self.post.unwrap_or(0) as i128 - self.pre.unwrap_or(0) as i128
+    }
+
+    /// Formats a raw token amount using the decimals of the mint.
+    pub fn format_amount(&self, amount: u64) -> String {
+        match self.decimals {
+            Some(decimals) => format_token_amount(amount as i128, decimals),
+            None => amount.to_string(),
+        }
+    }
+}
+
+/// The lamports and token state of an account at one point in time.
+#[derive(Clone, Debug)]
+pub(crate) struct AccountBalance {
+    pubkey: Pubkey,
+    lamports: u64,
+    token: Option<TokenAccount>,
+}
+
+impl AccountBalance {
+    /// Takes the balances of an account, which has no lamports if it does not exist.
+    pub(crate) fn new(pubkey: Pubkey, account: Option<&Account>) -> Self {
+        Self {
+            pubkey,
+            lamports: account.map_or(0, |account| account.lamports),
+            token: account.and_then(unpack_token_account),
+        }
+    }
+}
+
+/// Balances of the accounts of a transaction, before and after execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BalanceReport {
+    /// Lamport balances of every account of the transaction
+    pub lamports: Vec<LamportBalance>,
+    /// Token balances of every SPL token account of the transaction
+    pub tokens: Vec<TokenBalance>,
+}
+
+impl BalanceReport {
+    /// Builds a report from the balances of the transaction's accounts before and after execution.
+    ///
+    /// `svm` is used to look up mint decimals.
+    pub(crate) fn new(
+        svm: &LiteSVM,
+        pre_balances: &[AccountBalance],
+        post_balances: &[AccountBalance],
+    ) -> Self {
+        let mut report = Self::default();
+        for (pre, post) in pre_balances.iter().zip(post_balances) {
+            report.lamports.push(LamportBalance {
+                pubkey: pre.pubkey,
+                pre: pre.lamports,
+                post: post.lamports,
+            });
+
+            let (pre_token, post_token) = (pre.token, post.token);
+            let Some(token_account) = post_token.or(pre_token) else {
+                continue;
+            };
+            report.tokens.push(TokenBalance {
+                pubkey: pre.pubkey,
+                mint: token_account.mint,
+                owner: token_account.owner,
+                decimals: mint_decimals(svm, &token_account.mint),
+                pre: pre_token.map(|account| account.amount),
+                post: post_token.map(|account| account.amount),
+            });
+        }
+        report
+    }
+
+    /// Returns the lamport balance of the given account.
+    pub fn lamports(&self, pubkey: &Pubkey) -> Option<&LamportBalance> {
+        self.lamports
+            .iter()
+            .find(|balance| balance.pubkey == *pubkey)
+    }
+
+    /// Returns the token balance of the given token account.
+    pub fn token(&self, pubkey: &Pubkey) -> Option<&TokenBalance> {
+        self.tokens.iter().find(|balance| balance.pubkey == *pubkey)
+    }
+
+    /// Returns the token balance of the account holding `mint` for `owner`.
+    pub fn token_by_owner(&self, owner: &Pubkey, mint: &Pubkey) -> Option<&TokenBalance> {
+        self.tokens
+            .iter()
+            .find(|balance| balance.owner == *owner && balance.mint == *mint)
+    }
+
+    /// Print the balances, formatted using an [AddressBook].
+    pub fn print(&self, address_book: &AddressBook) {
+        println!("\n{} {}", "💰".green(), "SOL balances:".green().bold());
+        for balance in &self.lamports {
+            println!(
+                "   {}: {}",
+                address_book.format_address(&balance.pubkey),
+                format_change(
+                    format_token_amount(balance.pre as i128, 9),
+                    format_token_amount(balance.post as i128, 9),
+                    balance.change(),
+                    format_token_amount(balance.change(), 9)
+                )
+            );
+        }
+
+        if self.tokens.is_empty() {
+            return;
+        }
+        println!("\n{} {}", "🪙".green(), "Token balances:".green().bold());
+        for balance in &self.tokens {
+            let (mint, owner) = match address_book.get_first(&balance.pubkey) {
+                Some((_, registered)) => match &registered.role {
+                    AddressRole::Ata { mint, owner } => (*mint, *owner),
+                    _ => (balance.mint, balance.owner),
+                },
+                None => (balance.mint, balance.owner),
+            };
+            let format_amount = |amount: Option<u64>| match amount {
+                Some(amount) => balance.format_amount(amount),
+                None => "-".to_string(),
+            };
+            let change = match balance.decimals {
+                Some(decimals) => format_token_amount(balance.change(), decimals),
+                None => balance.change().to_string(),
+            };
+            println!(
+                "   {} ({} {} {}): {}",
+                address_book.format_address(&balance.pubkey),
+                address_book.get_label(&owner).bright_cyan(),
+                "/".dimmed(),
+                address_book.get_label(&mint).bright_green(),
+                format_change(
+                    format_amount(balance.pre),
+                    format_amount(balance.post),
+                    balance.change(),
+                    change
+                )
+            );
+        }
+    }
+}
+
+/// Decodes an SPL Token or Token-2022 account.
+fn unpack_token_account(account: &Account) -> Option<TokenAccount> {
+    if account.owner != anchor_spl::token::ID && account.owner != anchor_spl::token_2022::ID {
+        return None;
+    }
+    StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .ok()
+        .map(|state| state.base)
+}
+
+/// Looks up the decimals of a mint.
+fn mint_decimals(svm: &LiteSVM, mint: &Pubkey) -> Option<u8> {
+    let account = svm.get_account(mint)?;
+    StateWithExtensions::<Mint>::unpack(&account.data)
+        .ok()
+        .map(|state| state.base.decimals)
+}
+
+/// Formats a raw amount with the given number of decimals, e.g. `1500000` with 6 decimals as `1.5`.
+fn format_token_amount(amount: i128, decimals: u8) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    let amount = amount.unsigned_abs();
+    let divisor = 10u128.pow(decimals as u32);
+    let whole = amount / divisor;
+    let fraction = amount % divisor;
+    if fraction == 0 {
+        return format!("{sign}{whole}");
+    }
+    let fraction = format!("{fraction:0width$}", width = decimals as usize);
+    format!("{sign}{whole}.{}", fraction.trim_end_matches('0'))
+}
+
+/// Formats a before and after value, with the change if there is one.
+fn format_change(pre: String, post: String, change: i128, formatted_change: String) -> String {
+    match change {
+        0 => pre,
+        change if change > 0 => format!(
+            "{pre} → {post} ({})",
+            format!("+{formatted_change}").green()
+        ),
+        _ => format!("{pre} → {post} ({})", formatted_change.red()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestSVM;
+    use solana_sdk::{signature::Signer, system_instruction};
+
+    #[test]
+    fn test_format_token_amount() {
+        assert_eq!(format_token_amount(1_500_000, 6), "1.5");
+        assert_eq!(format_token_amount(-1_000_000_000, 9), "-1");
+        assert_eq!(format_token_amount(1, 6), "0.000001");
+        assert_eq!(format_token_amount(42, 0), "42");
+    }
+
+    #[test]
+    fn test_failed_transaction_reports_fee() -> anyhow::Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let bob = env.new_wallet("bob")?;
+
+        // Alice cannot afford this transfer, but the fee is still charged
+        let err = env
+            .execute_ixs_with_signers(
+                &[system_instruction::transfer(
+                    &alice.pubkey(),
+                    &bob.pubkey(),
+                    100_000_000_000,
+                )],
+                &[&alice],
+            )
+            .unwrap_err();
+
+        let fee_payer = err.balances.lamports(&env.default_fee_payer()).unwrap();
+        assert_eq!(fee_payer.change(), -10_000);
+        assert_eq!(err.balances.lamports(&alice.pubkey()).unwrap().change(), 0);
+        assert_eq!(err.balances.lamports(&bob.pubkey()).unwrap().change(), 0);
+        Ok(())
+    }
+}
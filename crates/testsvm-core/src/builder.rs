@@ -0,0 +1,374 @@
+//! # TestSVM Builder
+//!
+//! Configurable construction of [TestSVM] environments.
+//!
+//! [TestSVM::init] covers the common case. When a test needs a different payer,
+//! compute budget, feature set or runtime checks, or wants programs, fixtures and
+//! protocol setup applied up front, use a [TestSVMBuilder] instead.
+//!
+//! ## Plugins
+//!
+//! A [TestSVMPlugin] runs against the environment once it has been built. Any
+//! `Fn(&mut TestSVM) -> Result<()>` is a plugin, so existing setup functions such as
+//! `testsvm_quarry::setup_quarry_programs` can be passed directly.
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use litesvm::LiteSVM;
+use solana_compute_budget::compute_budget::ComputeBudget;
+use solana_sdk::{
+    feature_set::FeatureSet,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use crate::{
+    AddressBook, ClockConfig, ComputeUnitProfile, TXHistory, TestSVM, derive_keypair,
+    litesvm_helpers::airdrop,
+};
+
+/// Setup step that runs against a [TestSVM] when it is built.
+pub trait TestSVMPlugin {
+    /// Applies the plugin to the environment.
+    fn setup(&self, env: &mut TestSVM) -> Result<()>;
+}
+
+impl<F> TestSVMPlugin for F
+where
+    F: Fn(&mut TestSVM) -> Result<()>,
+{
+    fn setup(&self, env: &mut TestSVM) -> Result<()> {
+        self(env)
+    }
+}
+
+/// Builder for configuring a [TestSVM] environment.
+///
+/// # Example
+///
+/// ```
+/// use testsvm_core::prelude::*;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let payer = Keypair::new();
+/// let payer_pubkey = payer.pubkey();
+///
+/// let env = TestSVM::builder()
+///     .payer(payer)
+///     .payer_lamports(5_000_000_000)
+///     .sigverify(false)
+///     .plugin(|env: &mut TestSVM| {
+///         env.new_wallet("alice")?;
+///         Ok(())
+///     })
+///     .build()?;
+///
+/// assert_eq!(env.default_fee_payer(), payer_pubkey);
+/// assert_eq!(env.svm.get_balance(&payer_pubkey), Some(5_000_000_000));
+/// assert_eq!(env.address_book.get_all_by_role_type("wallet").len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TestSVMBuilder {
+    payer: Option<Keypair>,
+    payer_lamports: u64,
+    compute_budget: Option<ComputeBudget>,
+    sigverify: bool,
+    blockhash_check: bool,
+    feature_set: FeatureSet,
+    default_accounts: bool,
+    clock_config: ClockConfig,
+    print_account_diffs: bool,
+    profile_compute_units: bool,
+    keypair_seed: Option<u64>,
+    programs: Vec<(String, Pubkey, PathBuf)>,
+    program_fixtures: Vec<(String, Pubkey)>,
+    fixture_manifest: bool,
+    anchor_workspaces: Vec<PathBuf>,
+    account_fixtures: Vec<(String, PathBuf)>,
+    account_fixtures_dir: bool,
+    plugins: Vec<Box<dyn TestSVMPlugin>>,
+}
+
+impl Default for TestSVMBuilder {
+    fn default() -> Self {
+        Self {
+            payer: None,
+            payer_lamports: 1000 * LAMPORTS_PER_SOL,
+            compute_budget: None,
+            sigverify: true,
+            blockhash_check: true,
+            feature_set: FeatureSet::all_enabled(),
+            default_accounts: true,
+            clock_config: ClockConfig::default(),
+            print_account_diffs: false,
+            profile_compute_units: false,
+            keypair_seed: None,
+            programs: Vec::new(),
+            program_fixtures: Vec::new(),
+            fixture_manifest: false,
+            anchor_workspaces: Vec::new(),
+            account_fixtures: Vec::new(),
+            account_fixtures_dir: false,
+            plugins: Vec::new(),
+        }
+    }
+}
+
+impl TestSVMBuilder {
+    /// Creates a builder with the same defaults as [TestSVM::init].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the given keypair as the default fee payer instead of a random one.
+    pub fn payer(mut self, payer: Keypair) -> Self {
+        self.payer = Some(payer);
+        self
+    }
+
+    /// Sets the initial balance of the default fee payer. Defaults to 1000 SOL.
+    pub fn payer_lamports(mut self, lamports: u64) -> Self {
+        self.payer_lamports = lamports;
+        self
+    }
+
+    /// Sets the compute budget used for every transaction.
+    pub fn compute_budget(mut self, compute_budget: ComputeBudget) -> Self {
+        self.compute_budget = Some(compute_budget);
+        self
+    }
+
+    /// Enables or disables signature verification. Enabled by default.
+    pub fn sigverify(mut self, sigverify: bool) -> Self {
+        self.sigverify = sigverify;
+        self
+    }
+
+    /// Enables or disables the recent blockhash check. Enabled by default.
+    pub fn blockhash_check(mut self, blockhash_check: bool) -> Self {
+        self.blockhash_check = blockhash_check;
+        self
+    }
+
+    /// Sets the runtime feature set. Defaults to all features enabled.
+    pub fn feature_set(mut self, feature_set: FeatureSet) -> Self {
+        self.feature_set = feature_set;
+        self
+    }
+
+    /// Whether to register the system, token and associated token programs in the
+    /// address book. Enabled by default.
+    pub fn default_accounts(mut self, default_accounts: bool) -> Self {
+        self.default_accounts = default_accounts;
+        self
+    }
+
+    /// Sets the duration of a slot used when advancing the clock. Defaults to 400ms.
+    pub fn slot_duration(mut self, slot_duration: Duration) -> Self {
+        self.clock_config.slot_duration = slot_duration;
+        self
+    }
+
+    /// Whether to expire the latest blockhash whenever the clock is advanced. Disabled by default.
+    pub fn expire_blockhash_on_advance(mut self, expire_blockhash: bool) -> Self {
+        self.clock_config.expire_blockhash = expire_blockhash;
+        self
+    }
+
+    /// Whether to print the account changes of every successful transaction. Disabled by default.
+    pub fn print_account_diffs(mut self, print_account_diffs: bool) -> Self {
+        self.print_account_diffs = print_account_diffs;
+        self
+    }
+
+    /// Whether to record the compute units of every transaction. Disabled by default.
+    ///
+    /// See [TestSVM::print_compute_unit_profile].
+    pub fn profile_compute_units(mut self, profile_compute_units: bool) -> Self {
+        self.profile_compute_units = profile_compute_units;
+        self
+    }
+
+    /// Derives the payer, wallets, mints and other keypairs from a seed and their labels,
+    /// instead of generating them randomly. See [derive_keypair].
+    pub fn keypair_seed(mut self, seed: u64) -> Self {
+        self.keypair_seed = Some(seed);
+        self
+    }
+
+    /// Adds a program from a `.so` file. See [TestSVM::add_program_from_path].
+    pub fn program(mut self, label: &str, program_id: Pubkey, path: impl Into<PathBuf>) -> Self {
+        self.programs
+            .push((label.to_string(), program_id, path.into()));
+        self
+    }
+
+    /// Adds a program from the fixtures directory. See [TestSVM::add_program_fixture].
+    pub fn program_fixture(mut self, fixture_name: &str, program_id: Pubkey) -> Self {
+        self.program_fixtures
+            .push((fixture_name.to_string(), program_id));
+        self
+    }
+
+    /// Loads every program in `fixtures/manifest.toml`. See [TestSVM::load_fixture_manifest].
+    pub fn fixture_manifest(mut self) -> Self {
+        self.fixture_manifest = true;
+        self
+    }
+
+    /// Adds every program built in an Anchor workspace. See [TestSVM::add_anchor_workspace_programs].
+    pub fn anchor_workspace(mut self, path: impl Into<PathBuf>) -> Self {
+        self.anchor_workspaces.push(path.into());
+        self
+    }
+
+    /// Loads an account from a JSON dump. See [TestSVM::load_account_fixture].
+    pub fn account_fixture(mut self, label: &str, path: impl Into<PathBuf>) -> Self {
+        self.account_fixtures.push((label.to_string(), path.into()));
+        self
+    }
+
+    /// Loads every account in `fixtures/accounts/`. See [TestSVM::load_account_fixtures_dir].
+    pub fn account_fixtures_dir(mut self) -> Self {
+        self.account_fixtures_dir = true;
+        self
+    }
+
+    /// Adds a plugin to run once the environment is built.
+    ///
+    /// Plugins run in the order they were added, after all programs and fixtures are loaded.
+    pub fn plugin(mut self, plugin: impl TestSVMPlugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Builds the environment.
+    pub fn build(self) -> Result<TestSVM> {
+        let mut svm = LiteSVM::default()
+            .with_feature_set(self.feature_set)
+            .with_builtins()
+            .with_lamports(1_000_000u64.wrapping_mul(LAMPORTS_PER_SOL))
+            .with_sysvars()
+            .with_precompiles()
+            .with_spl_programs()
+            .with_sigverify(self.sigverify)
+            .with_blockhash_check(self.blockhash_check);
+        if let Some(compute_budget) = self.compute_budget {
+            svm = svm.with_compute_budget(compute_budget);
+        }
+
+        let default_fee_payer = self.payer.unwrap_or_else(|| match self.keypair_seed {
+            Some(seed) => derive_keypair(seed, "default_fee_payer"),
+            None => Keypair::new(),
+        });
+        airdrop(&mut svm, &default_fee_payer.pubkey(), self.payer_lamports)?;
+
+        let mut address_book = AddressBook::new();
+        if self.default_accounts {
+            address_book.add_default_accounts()?;
+        }
+
+        // Add the default fee payer to the address book
+        address_book.add_wallet(default_fee_payer.pubkey(), "default_fee_payer".to_string())?;
+
+        let mut env = TestSVM {
+            svm,
+            default_fee_payer,
+            address_book: Arc::new(address_book),
+            history: TXHistory::default(),
+            clock_config: self.clock_config,
+            print_account_diffs: self.print_account_diffs,
+            compute_unit_profile: self.profile_compute_units.then(ComputeUnitProfile::default),
+            keypair_seed: self.keypair_seed,
+        };
+
+        for (label, program_id, path) in &self.programs {
+            env.add_program_from_path(label, *program_id, path)?;
+        }
+        for (fixture_name, program_id) in &self.program_fixtures {
+            env.add_program_fixture(fixture_name, *program_id)?;
+        }
+        if self.fixture_manifest {
+            env.load_fixture_manifest()?;
+        }
+        for path in &self.anchor_workspaces {
+            env.add_anchor_workspace_programs(path)?;
+        }
+        for (label, path) in &self.account_fixtures {
+            env.load_account_fixture(label, path)?;
+        }
+        if self.account_fixtures_dir {
+            env.load_account_fixtures_dir()?;
+        }
+        for plugin in &self.plugins {
+            plugin.setup(&mut env)?;
+        }
+
+        Ok(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{message::Message, system_instruction, transaction::Transaction};
+
+    #[test]
+    fn test_builder_defaults_match_init() -> Result<()> {
+        let env = TestSVMBuilder::new().build()?;
+        assert_eq!(
+            env.svm.get_balance(&env.default_fee_payer()),
+            Some(1000 * LAMPORTS_PER_SOL)
+        );
+        assert!(env.svm.get_sigverify());
+        assert!(env.address_book.contains(&solana_sdk::system_program::ID));
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_without_default_accounts() -> Result<()> {
+        let env = TestSVMBuilder::new().default_accounts(false).build()?;
+        assert!(!env.address_book.contains(&solana_sdk::system_program::ID));
+        assert_eq!(env.address_book.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_sigverify_disabled() -> Result<()> {
+        let mut env = TestSVMBuilder::new().sigverify(false).build()?;
+        let recipient = Pubkey::new_unique();
+        let transfer_ix =
+            system_instruction::transfer(&env.default_fee_payer(), &recipient, LAMPORTS_PER_SOL);
+
+        // An unsigned transaction is accepted when sigverify is disabled
+        let mut transaction =
+            Transaction::new_unsigned(Message::new(&[transfer_ix], Some(&env.default_fee_payer())));
+        transaction.message.recent_blockhash = env.svm.latest_blockhash();
+        transaction.signatures = vec![solana_sdk::signature::Signature::new_unique()];
+        env.execute_transaction(transaction)?;
+
+        assert_eq!(env.svm.get_balance(&recipient), Some(LAMPORTS_PER_SOL));
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_runs_plugins_in_order() -> Result<()> {
+        let env = TestSVMBuilder::new()
+            .plugin(|env: &mut TestSVM| {
+                env.new_wallet("first")?;
+                Ok(())
+            })
+            .plugin(|env: &mut TestSVM| {
+                // Wallets from earlier plugins are already registered
+                assert_eq!(env.address_book.get_all_by_role_type("wallet").len(), 2);
+                env.new_wallet("second")?;
+                Ok(())
+            })
+            .build()?;
+        assert_eq!(env.address_book.get_all_by_role_type("wallet").len(), 3);
+        Ok(())
+    }
+}
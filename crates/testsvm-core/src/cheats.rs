@@ -0,0 +1,251 @@
+//! # Cheat Codes
+//!
+//! Direct writes to account state, bypassing programs entirely.
+//!
+//! Some states are slow or impossible to reach through real instructions, such as a
+//! reward period that ended long ago or an overflowing counter. These helpers write
+//! accounts straight into the SVM, so that tests can start from those states directly.
+//!
+//! ## Features
+//!
+//! - **Raw Accounts**: Replace an entire account with [TestSVM::set_account]
+//! - **Lamports and Owners**: Change a single field with [TestSVM::set_lamports] and [TestSVM::set_owner]
+//! - **Typed State**: Write Anchor account state with [AccountRef::store] and [AccountRef::modify]
+//! - **Impersonation**: Sign instructions as any address with [TestSVM::execute_ixs_as]
+
+use anchor_lang::{AccountDeserialize, AccountSerialize, Owner};
+use anyhow::*;
+use solana_sdk::{
+    account::Account, instruction::Instruction, message::Message, pubkey::Pubkey, signer::Signer,
+    system_program, transaction::Transaction,
+};
+
+use crate::{AccountRef, TXResult, TestSVM};
+
+impl TestSVM {
+    /// Writes an account into the SVM, replacing any existing account at `pubkey`.
+    pub fn set_account(&mut self, pubkey: &Pubkey, account: Account) -> Result<()> {
+        self.svm
+            .set_account(*pubkey, account)
+            .map_err(|e| anyhow!("Failed to set account {pubkey}: {e:?}"))
+    }
+
+    /// Sets the lamports of an account.
+    ///
+    /// If the account does not exist, it is created as an empty system account.
+    pub fn set_lamports(&mut self, pubkey: &Pubkey, lamports: u64) -> Result<()> {
+        let account = self.svm.get_account(pubkey).unwrap_or(Account {
+            owner: system_program::ID,
+            ..Default::default()
+        });
+        self.set_account(
+            pubkey,
+            Account {
+                lamports,
+                ..account
+            },
+        )
+    }
+
+    /// Sets the owner of an existing account.
+    pub fn set_owner(&mut self, pubkey: &Pubkey, owner: &Pubkey) -> Result<()> {
+        let account = self
+            .svm
+            .get_account(pubkey)
+            .with_context(|| format!("Account not found: {pubkey}"))?;
+        self.set_account(
+            pubkey,
+            Account {
+                owner: *owner,
+                ..account
+            },
+        )
+    }
+
+    /// Execute instructions as the given addresses, without their keypairs.
+    ///
+    /// Signature verification is disabled for this transaction only, so every address in
+    /// `impersonated` may sign without a valid signature. The transaction is still paid for
+    /// and signed by the test SVM's payer.
+    ///
+    /// Like [TestSVM::execute_v0_ixs], this returns a nested result. The outer error is
+    /// returned without executing anything if the instructions require a signature from an
+    /// address that is neither the payer nor impersonated. The inner [TXResult] is the outcome
+    /// of the transaction itself.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use testsvm_core::prelude::*;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut env = TestSVM::init()?;
+    /// let whale = Pubkey::new_unique();
+    /// let recipient = Pubkey::new_unique();
+    /// env.set_lamports(&whale, 1_000_000_000)?;
+    ///
+    /// let transfer_ix = solana_sdk::system_instruction::transfer(&whale, &recipient, 1_000);
+    /// env.execute_ixs_as(&[transfer_ix], &[whale])??;
+    /// assert_eq!(env.svm.get_balance(&recipient), Some(1_000));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_ixs_as(
+        &mut self,
+        instructions: &[Instruction],
+        impersonated: &[Pubkey],
+    ) -> Result<TXResult> {
+        let payer = self.default_fee_payer.pubkey();
+        let message =
+            Message::new_with_blockhash(instructions, Some(&payer), &self.svm.latest_blockhash());
+        let signers = &message.account_keys[..message.header.num_required_signatures as usize];
+        for signer in signers {
+            if *signer != payer && !impersonated.contains(signer) {
+                bail!(
+                    "Missing signer {}: it is neither the payer nor impersonated",
+                    self.address_book.format_address(signer)
+                );
+            }
+        }
+
+        let mut transaction = Transaction::new_unsigned(message);
+        transaction.partial_sign(&[&self.default_fee_payer], self.svm.latest_blockhash());
+
+        let guard = SigverifyGuard::disable(self);
+        Ok(guard.env.execute_transaction(transaction))
+    }
+}
+
+/// Disables signature verification, restoring the previous setting when dropped.
+struct SigverifyGuard<'a> {
+    env: &'a mut TestSVM,
+    sigverify: bool,
+}
+
+impl<'a> SigverifyGuard<'a> {
+    fn disable(env: &'a mut TestSVM) -> Self {
+        let sigverify = env.svm.get_sigverify();
+        env.svm = std::mem::take(&mut env.svm).with_sigverify(false);
+        Self { env, sigverify }
+    }
+}
+
+impl Drop for SigverifyGuard<'_> {
+    fn drop(&mut self) {
+        self.env.svm = std::mem::take(&mut self.env.svm).with_sigverify(self.sigverify);
+    }
+}
+
+impl<T: AccountSerialize + AccountDeserialize + Owner> AccountRef<T> {
+    /// Writes typed account state into the SVM, including its discriminator.
+    ///
+    /// The account is created if it does not exist, and is owned by `T::owner()`. Its data
+    /// grows to fit the serialized state but never shrinks, so that space allocated for
+    /// the account by its program is preserved. Lamports are topped up to keep the account
+    /// rent-exempt.
+    pub fn store(&self, env: &mut TestSVM, state: &T) -> Result<()> {
+        let mut serialized = Vec::new();
+        state
+            .try_serialize(&mut serialized)
+            .with_context(|| format!("Failed to serialize account {}", self.key))?;
+
+        let existing = env.svm.get_account(&self.key).unwrap_or_default();
+        let mut data = existing.data;
+        if data.len() < serialized.len() {
+            data.resize(serialized.len(), 0);
+        }
+        data[..serialized.len()].copy_from_slice(&serialized);
+
+        let lamports = existing
+            .lamports
+            .max(env.svm.minimum_balance_for_rent_exemption(data.len()));
+        env.set_account(
+            &self.key,
+            Account {
+                lamports,
+                data,
+                owner: T::owner(),
+                executable: false,
+                rent_epoch: existing.rent_epoch,
+            },
+        )
+    }
+
+    /// Loads the account state, applies `f` to it, and stores the result.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// miner.modify(&mut env, |miner| miner.rewards_earned = u64::MAX)?;
+    /// ```
+    pub fn modify(&self, env: &mut TestSVM, f: impl FnOnce(&mut T)) -> Result<()> {
+        let mut state = self.load(env)?;
+        f(&mut state);
+        self.store(env, &state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_lamports_and_owner() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        // Setting lamports creates a missing account
+        env.set_lamports(&pubkey, 1_000)?;
+        let account = env.svm.get_account(&pubkey).unwrap();
+        assert_eq!(account.lamports, 1_000);
+        assert_eq!(account.owner, system_program::ID);
+
+        env.set_owner(&pubkey, &owner)?;
+        let account = env.svm.get_account(&pubkey).unwrap();
+        assert_eq!(account.lamports, 1_000);
+        assert_eq!(account.owner, owner);
+
+        assert!(env.set_owner(&Pubkey::new_unique(), &owner).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_ixs_as() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let impersonated = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        env.set_lamports(&impersonated, 1_000_000_000)?;
+        let payer_balance = env.svm.get_balance(&env.default_fee_payer()).unwrap();
+
+        let transfer_ix =
+            solana_sdk::system_instruction::transfer(&impersonated, &recipient, 1_000);
+        env.execute_ixs_as(&[transfer_ix], &[impersonated])?
+            .map_err(|e| anyhow!("{}", e.metadata.err))?;
+        assert_eq!(env.svm.get_balance(&recipient), Some(1_000));
+        assert_eq!(env.svm.get_balance(&impersonated), Some(999_999_000));
+        // The real payer still pays the fee
+        assert!(env.svm.get_balance(&env.default_fee_payer()).unwrap() < payer_balance);
+
+        // Signers that are not impersonated are reported without executing anything
+        let transactions = env.history.len();
+        let transfer_ix =
+            solana_sdk::system_instruction::transfer(&recipient, &impersonated, 1_000);
+        assert!(env.execute_ixs_as(&[transfer_ix], &[impersonated]).is_err());
+        assert_eq!(env.history.len(), transactions);
+
+        // Signature verification is restored afterwards
+        assert!(env.svm.get_sigverify());
+        let transfer_ix =
+            solana_sdk::system_instruction::transfer(&impersonated, &recipient, 2_000);
+        let mut transaction =
+            Transaction::new_with_payer(&[transfer_ix], Some(&env.default_fee_payer()));
+        transaction.partial_sign(&[&env.default_fee_payer], env.svm.latest_blockhash());
+        let err = env.execute_transaction(transaction).unwrap_err();
+        assert_eq!(
+            err.metadata.err,
+            solana_sdk::transaction::TransactionError::SignatureFailure
+        );
+        Ok(())
+    }
+}
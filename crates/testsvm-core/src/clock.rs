@@ -0,0 +1,258 @@
+//! # Clock Control
+//!
+//! Coherent control over the `Clock` sysvar of a [TestSVM].
+//!
+//! LiteSVM never produces slots on its own, so time only moves when a test moves it.
+//! The helpers in this module always update the slot, epoch and timestamp together:
+//!
+//! - Slots and seconds are converted using the configured [ClockConfig::slot_duration]
+//! - The epoch, leader schedule epoch and `epoch_start_timestamp` follow the `EpochSchedule` sysvar
+//! - Optionally, the latest blockhash is expired on every advance, as it would be on a real cluster
+//!
+//! Conversions are anchored at slot zero and timestamp zero, so that many small advances
+//! add up to the same clock as a single large one.
+
+use std::time::Duration;
+
+use anyhow::*;
+use solana_sdk::{clock::Clock, epoch_schedule::EpochSchedule};
+
+use crate::TestSVM;
+
+/// Configuration of how time passes in a [TestSVM].
+#[derive(Clone, Debug)]
+pub struct ClockConfig {
+    /// Duration of a single slot. Defaults to 400ms.
+    pub slot_duration: Duration,
+    /// Whether to expire the latest blockhash whenever the clock is advanced. Defaults to `false`.
+    pub expire_blockhash: bool,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            slot_duration: Duration::from_millis(400),
+            expire_blockhash: false,
+        }
+    }
+}
+
+impl ClockConfig {
+    /// Returns the number of whole slots elapsed between two timestamps.
+    fn slots_between(&self, from_timestamp: i64, to_timestamp: i64) -> u64 {
+        let slot_ms = self.slot_duration.as_millis().max(1) as i128;
+        let slot_at = |timestamp: i64| (timestamp as i128 * 1000).div_euclid(slot_ms);
+        (slot_at(to_timestamp) - slot_at(from_timestamp)) as u64
+    }
+
+    /// Returns the number of whole seconds elapsed between two slots.
+    fn seconds_between(&self, from_slot: u64, to_slot: u64) -> i64 {
+        let slot_ms = self.slot_duration.as_millis() as i128;
+        let timestamp_at = |slot: u64| (slot as i128 * slot_ms).div_euclid(1000);
+        (timestamp_at(to_slot) - timestamp_at(from_slot)) as i64
+    }
+}
+
+impl TestSVM {
+    /// Advance the clock by the specified number of seconds
+    ///
+    /// The slot advances by the number of slots that fit in that time, according to the
+    /// configured slot duration.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use solana_sdk::clock::Clock;
+    /// use testsvm_core::prelude::*;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut env = TestSVM::init()?;
+    /// env.advance_time(60);
+    ///
+    /// let clock = env.svm.get_sysvar::<Clock>();
+    /// assert_eq!(clock.unix_timestamp, 60);
+    /// assert_eq!(clock.slot, 150); // 400ms slots
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn advance_time(&mut self, seconds: u64) {
+        let clock = self.svm.get_sysvar::<Clock>();
+        let unix_timestamp = clock.unix_timestamp + seconds as i64;
+        let slot = clock.slot
+            + self
+                .clock_config
+                .slots_between(clock.unix_timestamp, unix_timestamp);
+        self.set_clock(slot, unix_timestamp);
+    }
+
+    /// Advance the clock by the specified number of slots
+    ///
+    /// The timestamp advances by the duration of those slots.
+    pub fn advance_slots(&mut self, num_slots: u32) {
+        let current_slot = self.svm.get_sysvar::<Clock>().slot;
+        self.advance_to_slot(current_slot + num_slots as u64);
+    }
+
+    /// Warp the clock forward to the given slot
+    ///
+    /// Returns an error if the slot is in the past.
+    pub fn warp_to_slot(&mut self, slot: u64) -> Result<()> {
+        let clock = self.svm.get_sysvar::<Clock>();
+        if slot < clock.slot {
+            return Err(anyhow!(
+                "Cannot warp backwards from slot {} to slot {}",
+                clock.slot,
+                slot
+            ));
+        }
+        self.advance_to_slot(slot);
+        Ok(())
+    }
+
+    /// Warp the clock forward to the given unix timestamp
+    ///
+    /// Returns an error if the timestamp is in the past.
+    pub fn warp_to_timestamp(&mut self, unix_timestamp: i64) -> Result<()> {
+        let clock = self.svm.get_sysvar::<Clock>();
+        if unix_timestamp < clock.unix_timestamp {
+            return Err(anyhow!(
+                "Cannot warp backwards from timestamp {} to timestamp {}",
+                clock.unix_timestamp,
+                unix_timestamp
+            ));
+        }
+        self.advance_time((unix_timestamp - clock.unix_timestamp) as u64);
+        Ok(())
+    }
+
+    /// Warp the clock forward to the first slot of the given epoch
+    ///
+    /// Returns an error if the epoch is in the past.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use solana_sdk::{clock::Clock, epoch_schedule::EpochSchedule};
+    /// use testsvm_core::prelude::*;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut env = TestSVM::init()?;
+    /// env.warp_to_epoch(20)?;
+    ///
+    /// let clock = env.svm.get_sysvar::<Clock>();
+    /// let epoch_schedule = env.svm.get_sysvar::<EpochSchedule>();
+    /// assert_eq!(clock.epoch, 20);
+    /// assert_eq!(clock.slot, epoch_schedule.get_first_slot_in_epoch(20));
+    /// assert_eq!(clock.epoch_start_timestamp, clock.unix_timestamp);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn warp_to_epoch(&mut self, epoch: u64) -> Result<()> {
+        let clock = self.svm.get_sysvar::<Clock>();
+        if epoch < clock.epoch {
+            return Err(anyhow!(
+                "Cannot warp backwards from epoch {} to epoch {}",
+                clock.epoch,
+                epoch
+            ));
+        }
+        let epoch_schedule = self.svm.get_sysvar::<EpochSchedule>();
+        self.warp_to_slot(
+            epoch_schedule
+                .get_first_slot_in_epoch(epoch)
+                .max(clock.slot),
+        )
+    }
+
+    /// Move the clock to a later slot, advancing the timestamp by the duration of the skipped slots
+    fn advance_to_slot(&mut self, slot: u64) {
+        let clock = self.svm.get_sysvar::<Clock>();
+        let unix_timestamp =
+            clock.unix_timestamp + self.clock_config.seconds_between(clock.slot, slot);
+        self.set_clock(slot, unix_timestamp);
+    }
+
+    /// Set the slot and timestamp of the clock, deriving the epoch fields from the `EpochSchedule`
+    fn set_clock(&mut self, slot: u64, unix_timestamp: i64) {
+        let epoch_schedule = self.svm.get_sysvar::<EpochSchedule>();
+        let mut clock = self.svm.get_sysvar::<Clock>();
+
+        let epoch = epoch_schedule.get_epoch(slot);
+        if epoch != clock.epoch {
+            let epoch_start_slot = epoch_schedule.get_first_slot_in_epoch(epoch);
+            clock.epoch_start_timestamp =
+                unix_timestamp - self.clock_config.seconds_between(epoch_start_slot, slot);
+        }
+        clock.slot = slot;
+        clock.epoch = epoch;
+        clock.leader_schedule_epoch = epoch_schedule.get_leader_schedule_epoch(slot);
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+
+        if self.clock_config.expire_blockhash {
+            self.svm.expire_blockhash();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_advances_add_up() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        for _ in 0..5 {
+            env.advance_slots(1);
+        }
+        // 5 slots of 400ms
+        assert_eq!(env.svm.get_sysvar::<Clock>().unix_timestamp, 2);
+
+        for _ in 0..3 {
+            env.advance_time(1);
+        }
+        let clock = env.svm.get_sysvar::<Clock>();
+        assert_eq!(clock.unix_timestamp, 5);
+        assert_eq!(clock.slot, 12);
+        Ok(())
+    }
+
+    #[test]
+    fn test_advance_time_crosses_epochs() -> Result<()> {
+        let mut env = TestSVM::builder()
+            .slot_duration(Duration::from_secs(1))
+            .build()?;
+        let epoch_schedule = env.svm.get_sysvar::<EpochSchedule>();
+
+        env.advance_time(30 * 24 * 60 * 60);
+        let clock = env.svm.get_sysvar::<Clock>();
+        assert_eq!(clock.slot, 30 * 24 * 60 * 60);
+        assert_eq!(clock.epoch, epoch_schedule.get_epoch(clock.slot));
+        assert!(clock.epoch > 0);
+
+        let epoch_start_slot = epoch_schedule.get_first_slot_in_epoch(clock.epoch);
+        assert_eq!(clock.epoch_start_timestamp, epoch_start_slot as i64);
+        Ok(())
+    }
+
+    #[test]
+    fn test_warp_to_timestamp() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        env.warp_to_timestamp(1_700_000_000)?;
+        assert_eq!(env.svm.get_sysvar::<Clock>().unix_timestamp, 1_700_000_000);
+        assert!(env.warp_to_timestamp(0).is_err());
+        assert!(env.warp_to_epoch(0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_blockhash_on_advance() -> Result<()> {
+        let mut env = TestSVM::builder()
+            .expire_blockhash_on_advance(true)
+            .build()?;
+        let blockhash = env.svm.latest_blockhash();
+        env.advance_slots(1);
+        assert_ne!(env.svm.latest_blockhash(), blockhash);
+        Ok(())
+    }
+}
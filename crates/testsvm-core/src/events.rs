@@ -0,0 +1,208 @@
+//! # Anchor Events
+//!
+//! Typed decoding of Anchor events emitted by a transaction.
+//!
+//! Anchor programs emit events in one of two ways:
+//!
+//! - `emit!` logs the serialized event as a base64 `Program data:` line
+//! - `emit_cpi!` invokes the program itself through its event authority, with the
+//!   serialized event as instruction data prefixed by `EVENT_IX_TAG_LE`
+//!
+//! Both are decoded by [TXSuccess::events] and [TXError::events]. Events are matched by
+//! their discriminator, so only events of the requested type are returned.
+
+use anchor_lang::{Event, event::EVENT_IX_TAG_LE};
+use anyhow::*;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use litesvm::types::TransactionMetadata;
+use solana_sdk::{message::AccountKeys, pubkey::Pubkey, transaction::VersionedTransaction};
+
+use crate::{TXError, TXSuccess};
+
+/// Seed of the PDA through which `emit_cpi!` programs invoke themselves.
+const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+/// Decodes every event of type `E` from the logs and inner instructions of a transaction.
+///
+/// Events emitted with `emit!` are returned first, followed by those emitted with `emit_cpi!`,
+/// each in the order they were emitted.
+fn decode_events<E: Event>(
+    transaction: &VersionedTransaction,
+    account_keys: &AccountKeys,
+    metadata: &TransactionMetadata,
+) -> Result<Vec<E>> {
+    // As in Anchor's client, lines that are not a single base64 string are skipped. They are
+    // logged by `sol_log_data` calls with several slices, rather than by `emit!`.
+    let logged = metadata
+        .logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| BASE64.decode(data).ok());
+
+    logged
+        .chain(self_invoked_event_data(transaction, account_keys, metadata))
+        .filter_map(|data| {
+            data.strip_prefix(E::DISCRIMINATOR)
+                .map(|data| data.to_vec())
+        })
+        .map(|data| {
+            E::deserialize(&mut data.as_slice())
+                .map_err(|e| anyhow!("Failed to deserialize event: {e}"))
+        })
+        .collect()
+}
+
+/// Returns the event data of every `emit_cpi!` call: an inner instruction tagged with
+/// `EVENT_IX_TAG_LE` through which a program invokes itself with its event authority.
+fn self_invoked_event_data(
+    transaction: &VersionedTransaction,
+    account_keys: &AccountKeys,
+    metadata: &TransactionMetadata,
+) -> Vec<Vec<u8>> {
+    let mut events = Vec::new();
+    for (instruction, inner_instructions) in transaction
+        .message
+        .instructions()
+        .iter()
+        .zip(&metadata.inner_instructions)
+    {
+        // Programs on the invocation stack, indexed by stack height minus one
+        let mut stack = vec![instruction.program_id_index];
+        for inner in inner_instructions {
+            let inner_ix = &inner.instruction;
+            stack.truncate((inner.stack_height as usize).saturating_sub(1));
+            let caller = stack.last().copied();
+            stack.push(inner_ix.program_id_index);
+
+            let Some(data) = inner_ix.data.strip_prefix(EVENT_IX_TAG_LE) else {
+                continue;
+            };
+            if caller != Some(inner_ix.program_id_index) {
+                continue;
+            }
+            let Some(program_id) = account_keys.get(inner_ix.program_id_index as usize) else {
+                continue;
+            };
+            let (event_authority, _) =
+                Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], program_id);
+            if inner_ix
+                .accounts
+                .iter()
+                .any(|index| account_keys.get(*index as usize) == Some(&event_authority))
+            {
+                events.push(data.to_vec());
+            }
+        }
+    }
+    events
+}
+
+impl TXSuccess {
+    /// Returns every event of type `E` emitted by the transaction.
+    ///
+    /// Both `emit!` and `emit_cpi!` events are decoded.
+    pub fn events<E: Event>(&self) -> Result<Vec<E>> {
+        decode_events(&self.transaction, &self.account_keys(), &self.metadata)
+    }
+}
+
+impl TXError {
+    /// Returns every event of type `E` emitted by the transaction before it failed.
+    ///
+    /// Both `emit!` and `emit_cpi!` events are decoded.
+    pub fn events<E: Event>(&self) -> Result<Vec<E>> {
+        decode_events(&self.transaction, &self.account_keys(), &self.metadata.meta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EVENT_AUTHORITY_SEED, decode_events};
+    use anchor_lang::{Event, event::EVENT_IX_TAG_LE, prelude::*};
+    use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+    use litesvm::types::TransactionMetadata;
+    use solana_sdk::{
+        inner_instruction::InnerInstruction,
+        instruction::CompiledInstruction,
+        message::{AccountKeys, Message, VersionedMessage},
+        transaction::VersionedTransaction,
+    };
+
+    #[event]
+    #[derive(Debug, PartialEq)]
+    struct TestEvent {
+        amount: u64,
+    }
+
+    fn event_cpi_data(event: &TestEvent) -> Vec<u8> {
+        let mut data = EVENT_IX_TAG_LE.to_vec();
+        data.extend(event.data());
+        data
+    }
+
+    fn inner_instruction(
+        program_id_index: u8,
+        data: Vec<u8>,
+        accounts: Vec<u8>,
+    ) -> InnerInstruction {
+        InnerInstruction {
+            instruction: CompiledInstruction::new_from_raw_parts(program_id_index, data, accounts),
+            stack_height: 2,
+        }
+    }
+
+    #[test]
+    fn test_decode_logged_and_self_invoked_events() -> anyhow::Result<()> {
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+        let (event_authority, _) =
+            Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &program_id);
+        let account_keys = vec![
+            Pubkey::new_unique(),
+            program_id,
+            event_authority,
+            other_program_id,
+        ];
+        let transaction = VersionedTransaction {
+            signatures: vec![],
+            message: VersionedMessage::Legacy(Message {
+                account_keys: account_keys.clone(),
+                instructions: vec![CompiledInstruction::new_from_raw_parts(1, vec![], vec![2])],
+                ..Default::default()
+            }),
+        };
+
+        let logged = TestEvent { amount: 1 };
+        let self_invoked = TestEvent { amount: 2 };
+        let metadata = TransactionMetadata {
+            logs: vec![
+                "Program log: Instruction: Claim".to_string(),
+                format!("Program data: {}", BASE64.encode(logged.data())),
+                // Data logged by other means is ignored
+                format!("Program data: {}", BASE64.encode([1, 2, 3])),
+                // As are several slices logged by a single `sol_log_data` call
+                format!(
+                    "Program data: {} {}",
+                    BASE64.encode([1, 2, 3]),
+                    BASE64.encode([4, 5, 6])
+                ),
+            ],
+            inner_instructions: vec![vec![
+                inner_instruction(1, event_cpi_data(&self_invoked), vec![2]),
+                // Tagged instructions to another program are not events of the caller
+                inner_instruction(3, event_cpi_data(&TestEvent { amount: 3 }), vec![2]),
+                // Nor are self-invocations without the event authority
+                inner_instruction(1, event_cpi_data(&TestEvent { amount: 4 }), vec![0]),
+            ]],
+            ..Default::default()
+        };
+
+        let events = decode_events::<TestEvent>(
+            &transaction,
+            &AccountKeys::new(&account_keys, None),
+            &metadata,
+        )?;
+        assert_eq!(events, vec![logged, self_invoked]);
+        Ok(())
+    }
+}
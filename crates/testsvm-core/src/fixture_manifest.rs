@@ -0,0 +1,267 @@
+//! # Fixture Manifest
+//!
+//! Declarative loading of program fixtures from `fixtures/manifest.toml`.
+//!
+//! The manifest lists every program binary in `fixtures/programs/` along with its program id
+//! and label, and optionally the SHA-256 of the binary and the cluster and slot it was dumped
+//! from. [TestSVM::load_fixture_manifest] loads every listed program, so that setup code does
+//! not need to repeat program ids, and verifies the hashes so that stale or corrupted binaries
+//! are reported up front rather than as confusing runtime errors. [TestSVM::add_program_fixture]
+//! checks a single binary against its manifest entry in the same way.
+//!
+//! ```toml
+//! [[programs]]
+//! file = "quarry_mine.so"
+//! program_id = "QMNeHCGYnLVDn1icRAfQZpjPLBNkfGbSKRB83G5d8KB"
+//! label = "quarry_mine"
+//! sha256 = "45a730d14cdb827861a721f9e30eb6a90fff09c216d6e5b12bbcc6b066ca3305"
+//! cluster = "mainnet-beta"
+//! slot = 369000000
+//! ```
+//!
+//! The hash of a binary can be computed with `sha256sum fixtures/programs/quarry_mine.so`.
+
+use std::{fmt::Write, fs, path::Path, sync::OnceLock};
+
+use anyhow::*;
+use serde::Deserialize;
+use solana_sdk::{hash::hash, pubkey::Pubkey};
+
+use crate::{TestSVM, fixtures::fixtures_dir};
+
+/// The contents of `fixtures/manifest.toml`.
+#[derive(Debug, Deserialize)]
+struct FixtureManifest {
+    #[serde(default)]
+    programs: Vec<ProgramFixture>,
+}
+
+/// A program binary in `fixtures/programs/`.
+#[derive(Debug, Deserialize)]
+struct ProgramFixture {
+    /// File name of the binary, relative to `fixtures/programs/`
+    file: String,
+    program_id: String,
+    label: String,
+    /// Expected hex-encoded SHA-256 of the binary
+    sha256: Option<String>,
+    /// Cluster the binary was dumped from
+    cluster: Option<String>,
+    /// Slot the binary was dumped at
+    slot: Option<u64>,
+}
+
+impl ProgramFixture {
+    /// Describes where the binary was dumped from, if known.
+    fn source(&self) -> String {
+        match (&self.cluster, self.slot) {
+            (Some(cluster), Some(slot)) => format!(" (dumped from {cluster} at slot {slot})"),
+            (Some(cluster), None) => format!(" (dumped from {cluster})"),
+            (None, Some(slot)) => format!(" (dumped at slot {slot})"),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Parses the program id of the binary.
+    fn program_id(&self) -> Result<Pubkey> {
+        self.program_id.parse().with_context(|| {
+            format!(
+                "Invalid program id '{}' for fixture '{}'",
+                self.program_id, self.file
+            )
+        })
+    }
+
+    /// Checks the binary at `path` against the expected SHA-256, if the manifest lists one.
+    fn verify(&self, path: &Path, program: &[u8]) -> Result<()> {
+        if let Some(expected) = &self.sha256 {
+            let actual = sha256_hex(program);
+            if !actual.eq_ignore_ascii_case(expected) {
+                bail!(
+                    "Program fixture {} has SHA-256 {actual}, but the manifest expects {expected}{}. The binary is stale or corrupted; dump it again.",
+                    path.display(),
+                    self.source()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the contents of a fixture manifest.
+fn parse_fixture_manifest(contents: &str) -> Result<FixtureManifest> {
+    toml::from_str(contents).context("Failed to parse fixture manifest")
+}
+
+/// Computes the hex-encoded SHA-256 of a binary.
+fn sha256_hex(data: &[u8]) -> String {
+    hash(data)
+        .to_bytes()
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// The parsed `fixtures/manifest.toml`, or `None` if there is no manifest.
+static FIXTURE_MANIFEST: OnceLock<Option<FixtureManifest>> = OnceLock::new();
+
+/// Reads and parses `fixtures/manifest.toml` the first time it is needed.
+fn fixture_manifest() -> Result<Option<&'static FixtureManifest>> {
+    if let Some(manifest) = FIXTURE_MANIFEST.get() {
+        return Ok(manifest.as_ref());
+    }
+    let manifest_path = fixtures_dir()?.join("manifest.toml");
+    let manifest = if manifest_path.exists() {
+        let contents = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+        let manifest = parse_fixture_manifest(&contents)
+            .with_context(|| format!("Invalid {}", manifest_path.display()))?;
+        Some(manifest)
+    } else {
+        None
+    };
+    Ok(FIXTURE_MANIFEST.get_or_init(|| manifest).as_ref())
+}
+
+/// Checks a program binary loaded from `path` against its entry in `fixtures/manifest.toml`.
+///
+/// Nothing is checked if there is no manifest, or if it does not list the binary.
+pub(crate) fn verify_program_fixture(
+    path: &Path,
+    program_id: &Pubkey,
+    program: &[u8],
+) -> Result<()> {
+    let Some(manifest) = fixture_manifest()? else {
+        return Ok(());
+    };
+    let Some(fixture) = manifest
+        .programs
+        .iter()
+        .find(|fixture| path.file_name() == Some(fixture.file.as_ref()))
+    else {
+        return Ok(());
+    };
+
+    let expected = fixture.program_id()?;
+    if *program_id != expected {
+        bail!(
+            "Program fixture {} is loaded at {program_id}, but the manifest lists it at {expected}",
+            path.display()
+        );
+    }
+    fixture.verify(path, program)
+}
+
+impl TestSVM {
+    /// Loads every program listed in `fixtures/manifest.toml` and adds it to the address book.
+    ///
+    /// Returns the ids of the loaded programs, in the order they are listed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a binary is missing, or if its SHA-256 does not match the one
+    /// listed in the manifest. Dump the program again to fix a stale binary.
+    pub fn load_fixture_manifest(&mut self) -> Result<Vec<Pubkey>> {
+        let fixtures_dir = fixtures_dir()?;
+        let manifest = fixture_manifest()?.with_context(|| {
+            format!("{} not found", fixtures_dir.join("manifest.toml").display())
+        })?;
+
+        manifest
+            .programs
+            .iter()
+            .map(|fixture| {
+                let program_id = fixture.program_id()?;
+                let path = fixtures_dir.join("programs").join(&fixture.file);
+                let program = fs::read(&path).with_context(|| {
+                    format!(
+                        "Failed to read program fixture {}{}",
+                        path.display(),
+                        fixture.source()
+                    )
+                })?;
+
+                fixture.verify(&path, &program)?;
+
+                self.svm.add_program(program_id, &program);
+                self.address_book_mut()
+                    .add_program(program_id, &fixture.label)?;
+                Ok(program_id)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fixture_manifest() -> Result<()> {
+        let manifest = parse_fixture_manifest(
+            r#"
+            [[programs]]
+            file = "my_program.so"
+            program_id = "11111111111111111111111111111111"
+            label = "my_program"
+            sha256 = "abc123"
+            cluster = "mainnet-beta"
+            slot = 42
+
+            [[programs]]
+            file = "other_program.so"
+            program_id = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+            label = "other_program"
+            "#,
+        )?;
+
+        assert_eq!(manifest.programs.len(), 2);
+        assert_eq!(manifest.programs[0].sha256.as_deref(), Some("abc123"));
+        assert_eq!(
+            manifest.programs[0].source(),
+            " (dumped from mainnet-beta at slot 42)"
+        );
+        assert_eq!(manifest.programs[1].sha256, None);
+        assert_eq!(manifest.programs[1].source(), "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_load_fixture_manifest() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let program_ids = env.load_fixture_manifest()?;
+
+        assert_eq!(program_ids.len(), 3);
+        for program_id in &program_ids {
+            assert!(env.svm.get_account(program_id).unwrap().executable);
+        }
+        assert_eq!(env.address_book.get_label(&program_ids[0]), "quarry_mine");
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_program_fixture_checks_manifest() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let quarry_mine: Pubkey = "QMNeHCGYnLVDn1icRAfQZpjPLBNkfGbSKRB83G5d8KB".parse()?;
+        env.add_program_fixture("quarry_mine", quarry_mine)?;
+
+        let error = env
+            .add_program_fixture("quarry_mine", Pubkey::new_unique())
+            .unwrap_err();
+        assert!(error.to_string().contains("but the manifest lists it at"));
+
+        // Binaries missing from the manifest are not checked
+        verify_program_fixture(Path::new("unlisted_program.so"), &quarry_mine, b"")?;
+        Ok(())
+    }
+}
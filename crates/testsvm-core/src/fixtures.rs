@@ -0,0 +1,221 @@
+//! # Account Fixtures
+//!
+//! Loading of accounts dumped from a live cluster into the test environment.
+//!
+//! Account fixtures use the JSON format written by `solana account --output json`,
+//! which makes it possible to test against real on-chain state offline. By convention,
+//! fixtures live in `fixtures/accounts/*.json`, next to the program binaries in
+//! `fixtures/programs/*.so`.
+//!
+//! ```bash
+//! solana account <ADDRESS> --output json --output-file $ROOT_DIR/fixtures/accounts/my_account.json
+//! ```
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::*;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::Deserialize;
+use solana_address_book::{AddressBook, RegisteredAddress};
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey, system_program};
+
+use crate::TestSVM;
+
+/// An account dump as written by `solana account --output json`.
+#[derive(Debug, Deserialize)]
+struct AccountFixture {
+    pubkey: String,
+    account: UiAccountFixture,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UiAccountFixture {
+    lamports: u64,
+    /// Tuple of `[data, encoding]`
+    data: (String, String),
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+}
+
+/// Finds the `fixtures` directory by walking up from `CARGO_MANIFEST_DIR`.
+pub(crate) fn fixtures_dir() -> Result<PathBuf> {
+    env::var("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .map_err(|e| anyhow!("Failed to get environment variable `CARGO_MANIFEST_DIR`: {e}"))?
+        .ancestors()
+        .find_map(|ancestor| {
+            let fixtures_dir = ancestor.join("fixtures");
+            fixtures_dir.exists().then_some(fixtures_dir)
+        })
+        .ok_or_else(|| anyhow!("`fixtures` directory not found"))
+}
+
+/// Parses a `solana account --output json` dump into its address and account.
+fn parse_account_fixture(json: &str) -> Result<(Pubkey, Account)> {
+    let fixture: AccountFixture =
+        serde_json::from_str(json).context("Failed to parse account fixture JSON")?;
+    let (data, encoding) = &fixture.account.data;
+    if encoding != "base64" {
+        return Err(anyhow!(
+            "Unsupported account data encoding '{encoding}', expected 'base64'"
+        ));
+    }
+
+    let pubkey: Pubkey = fixture
+        .pubkey
+        .parse()
+        .with_context(|| format!("Invalid pubkey '{}'", fixture.pubkey))?;
+    let owner: Pubkey = fixture
+        .account
+        .owner
+        .parse()
+        .with_context(|| format!("Invalid owner '{}'", fixture.account.owner))?;
+
+    Ok((
+        pubkey,
+        Account {
+            lamports: fixture.account.lamports,
+            data: BASE64
+                .decode(data)
+                .context("Failed to decode account data")?,
+            owner,
+            executable: fixture.account.executable,
+            rent_epoch: fixture.account.rent_epoch,
+        },
+    ))
+}
+
+/// Infers the address book role of an account from its owner and data.
+///
+/// Token program accounts are registered as mints or ATAs where possible; accounts
+/// owned by any other program get a custom role named after the owning program.
+fn infer_registered_address(
+    address_book: &AddressBook,
+    pubkey: Pubkey,
+    account: &Account,
+) -> RegisteredAddress {
+    if account.executable {
+        return RegisteredAddress::program(pubkey);
+    }
+    if account.owner == system_program::ID {
+        return RegisteredAddress::wallet(pubkey);
+    }
+    if account.owner == anchor_spl::token::ID {
+        if account.data.len() == anchor_spl::token::Mint::LEN {
+            return RegisteredAddress::mint(pubkey);
+        }
+        if let std::result::Result::Ok(token_account) =
+            anchor_spl::token::spl_token::state::Account::unpack(&account.data)
+        {
+            let ata = anchor_spl::associated_token::get_associated_token_address(
+                &token_account.owner,
+                &token_account.mint,
+            );
+            if ata == pubkey {
+                return RegisteredAddress::ata(pubkey, token_account.mint, token_account.owner);
+            }
+            return RegisteredAddress::custom(pubkey, "token_account");
+        }
+    }
+    RegisteredAddress::custom(pubkey, &address_book.get_label(&account.owner))
+}
+
+impl TestSVM {
+    /// Loads an account from a `solana account --output json` dump into the SVM.
+    ///
+    /// The account is registered in the address book under `label`, with a role inferred
+    /// from its owner: system accounts become wallets, token program accounts become mints
+    /// or ATAs, and other accounts get a custom role named after their owning program.
+    /// Load the owning programs first so that their labels are used.
+    pub fn load_account_fixture(&mut self, label: &str, path: impl AsRef<Path>) -> Result<Pubkey> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read account fixture {}", path.display()))?;
+        let (pubkey, account) = parse_account_fixture(&json)
+            .with_context(|| format!("Invalid account fixture {}", path.display()))?;
+
+        let registered_address = infer_registered_address(&self.address_book, pubkey, &account);
+        self.svm
+            .set_account(pubkey, account)
+            .map_err(|e| anyhow!("Failed to set account {pubkey}: {e:?}"))?;
+        self.address_book_mut()
+            .add(pubkey, label.to_string(), registered_address)?;
+
+        Ok(pubkey)
+    }
+
+    /// Loads every account fixture in the fixtures directory.
+    ///
+    /// Fixtures are read from `fixtures/accounts/*.json` relative to your project root,
+    /// and each account is labeled with its file name (without the extension).
+    pub fn load_account_fixtures_dir(&mut self) -> Result<Vec<Pubkey>> {
+        let accounts_dir = fixtures_dir()?.join("accounts");
+        let mut paths = fs::read_dir(&accounts_dir)
+            .with_context(|| format!("Failed to read {}", accounts_dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+        paths.sort();
+
+        paths
+            .iter()
+            .map(|path| {
+                let label = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .ok_or_else(|| anyhow!("Invalid fixture file name {}", path.display()))?;
+                self.load_account_fixture(label, path)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_account_fixture() -> Result<()> {
+        let json = r#"{
+            "pubkey": "So11111111111111111111111111111111111111112",
+            "account": {
+                "lamports": 1461600,
+                "data": ["AQID", "base64"],
+                "owner": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+                "executable": false,
+                "rentEpoch": 18446744073709551615,
+                "space": 3
+            }
+        }"#;
+
+        let (pubkey, account) = parse_account_fixture(json)?;
+        assert_eq!(pubkey, anchor_spl::token::spl_token::native_mint::ID);
+        assert_eq!(account.lamports, 1461600);
+        assert_eq!(account.data, vec![1, 2, 3]);
+        assert_eq!(account.owner, anchor_spl::token::ID);
+        assert_eq!(account.rent_epoch, u64::MAX);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_account_fixture_rejects_base58() {
+        let json = r#"{
+            "pubkey": "So11111111111111111111111111111111111111112",
+            "account": {
+                "lamports": 1,
+                "data": ["2", "base58"],
+                "owner": "11111111111111111111111111111111",
+                "executable": false,
+                "rentEpoch": 0,
+                "space": 1
+            }
+        }"#;
+
+        assert!(parse_account_fixture(json).is_err());
+    }
+}
@@ -0,0 +1,400 @@
+//! # Transaction History
+//!
+//! An ordered, in-memory ledger of every transaction executed by a [TestSVM].
+//!
+//! When a long scenario fails, the failing transaction alone rarely tells the whole
+//! story. The history records each transaction executed through
+//! [TestSVM::execute_transaction] (and the helpers built on it), successful or not,
+//! so that the steps leading up to a failure can be inspected or printed.
+//!
+//! ## Features
+//!
+//! - **Named Steps**: Label the next transaction with [TestSVM::name_next_transaction]
+//! - **Full Context**: Signature, clock, instructions, labeled accounts, result, compute units and logs
+//! - **Queries**: Look up transactions by position or name
+//! - **Pretty Printing**: [TestSVM::print_history] labels every address using the address book
+
+use colored::Colorize;
+use litesvm::types::TransactionMetadata;
+use solana_address_book::AddressBook;
+use solana_sdk::{
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    message::{AccountKeys, v0::LoadedAddresses},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{TransactionError, VersionedTransaction},
+};
+
+use crate::{TestSVM, lookup_tables::is_writable};
+
+/// A single transaction recorded in the [TXHistory].
+#[derive(Clone, Debug)]
+pub struct TXHistoryEntry {
+    /// Name given with [TestSVM::name_next_transaction], if any
+    pub name: Option<String>,
+    /// Signature of the transaction
+    pub signature: Signature,
+    /// Clock at the time the transaction was executed
+    pub clock: Clock,
+    /// Instructions of the transaction, with lookup table addresses resolved
+    pub instructions: Vec<Instruction>,
+    /// All accounts referenced by the transaction, with their labels at execution time
+    pub accounts: Vec<(Pubkey, String)>,
+    /// Whether the transaction succeeded, or the error it failed with
+    pub result: Result<(), TransactionError>,
+    /// Compute units consumed by the transaction
+    pub compute_units_consumed: u64,
+    /// Program logs of the transaction
+    pub logs: Vec<String>,
+}
+
+impl TXHistoryEntry {
+    /// Returns whether the transaction succeeded.
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Returns the slot the transaction was executed in.
+    pub fn slot(&self) -> u64 {
+        self.clock.slot
+    }
+
+    /// Print the entry, formatted using an [AddressBook].
+    ///
+    /// Logs are only printed for failed transactions.
+    pub fn print(&self, index: usize, address_book: &AddressBook) {
+        let status = match &self.result {
+            Ok(()) => "✅".to_string(),
+            Err(err) => format!("❌ {}", format!("{err:?}").bright_red()),
+        };
+        println!(
+            "{} {} {}",
+            format!("#{index}").bold(),
+            self.name.as_deref().unwrap_or("<unnamed>").bold(),
+            status
+        );
+        println!(
+            "   {} {}  {} {}  {} {}  {} {}",
+            "Slot:".dimmed(),
+            self.clock.slot,
+            "Time:".dimmed(),
+            self.clock.unix_timestamp,
+            "CUs:".dimmed(),
+            self.compute_units_consumed.to_string().cyan(),
+            "Signature:".dimmed(),
+            self.signature.to_string().dimmed()
+        );
+
+        for (i, ix) in self.instructions.iter().enumerate() {
+            println!(
+                "   {} {}: {}",
+                "Instruction".dimmed(),
+                i.to_string().bold(),
+                address_book.format_address(&ix.program_id)
+            );
+            for account in &ix.accounts {
+                let mut flags = Vec::new();
+                if account.is_signer {
+                    flags.push("signer".green().to_string());
+                }
+                if account.is_writable {
+                    flags.push("writable".yellow().to_string());
+                }
+                let flags_str = if !flags.is_empty() {
+                    format!(" [{}]", flags.join(", "))
+                } else {
+                    String::new()
+                };
+                println!(
+                    "     {}{}",
+                    address_book.format_address(&account.pubkey),
+                    flags_str
+                );
+            }
+        }
+
+        if !self.is_success() {
+            println!("   {}", "Logs:".dimmed());
+            for log in &self.logs {
+                println!("     {}", address_book.replace_addresses_in_text(log));
+            }
+        }
+    }
+}
+
+/// Ordered log of the transactions executed by a [TestSVM].
+#[derive(Clone, Debug, Default)]
+pub struct TXHistory {
+    entries: Vec<TXHistoryEntry>,
+    next_name: Option<String>,
+}
+
+impl TXHistory {
+    /// Returns all recorded transactions, oldest first.
+    pub fn entries(&self) -> &[TXHistoryEntry] {
+        &self.entries
+    }
+
+    /// Returns the most recently executed transaction.
+    pub fn last(&self) -> Option<&TXHistoryEntry> {
+        self.entries.last()
+    }
+
+    /// Returns the most recent transaction with the given name.
+    pub fn find(&self, name: &str) -> Option<&TXHistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.name.as_deref() == Some(name))
+    }
+
+    /// Returns all failed transactions, oldest first.
+    pub fn failures(&self) -> impl Iterator<Item = &TXHistoryEntry> {
+        self.entries.iter().filter(|entry| !entry.is_success())
+    }
+
+    /// Returns the number of recorded transactions.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no transactions have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all recorded transactions.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.next_name = None;
+    }
+
+    /// Print every recorded transaction, formatted using an [AddressBook].
+    pub fn print(&self, address_book: &AddressBook) {
+        println!("\n{}", "═".repeat(80).dimmed());
+        println!(
+            "📚 {} ({} transactions):",
+            "Transaction History".bold(),
+            self.entries.len()
+        );
+        println!("{}", "─".repeat(80).dimmed());
+        for (i, entry) in self.entries.iter().enumerate() {
+            entry.print(i, address_book);
+        }
+        println!("{}", "═".repeat(80).dimmed());
+    }
+
+    /// Records an executed transaction, consuming the pending name.
+    pub(crate) fn record(
+        &mut self,
+        transaction: &VersionedTransaction,
+        loaded_addresses: &LoadedAddresses,
+        clock: Clock,
+        result: Result<(), TransactionError>,
+        metadata: &TransactionMetadata,
+        address_book: &AddressBook,
+    ) {
+        let message = &transaction.message;
+        let account_keys = AccountKeys::new(message.static_account_keys(), Some(loaded_addresses));
+        // Accounts from lookup tables that could not be resolved are recorded as the default key
+        let account_key = |index: usize| account_keys.get(index).copied().unwrap_or_default();
+        let instructions = message
+            .instructions()
+            .iter()
+            .map(|ix| Instruction {
+                program_id: account_key(ix.program_id_index as usize),
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|index| {
+                        let index = *index as usize;
+                        AccountMeta {
+                            pubkey: account_key(index),
+                            is_signer: message.is_signer(index),
+                            is_writable: is_writable(message, loaded_addresses, index),
+                        }
+                    })
+                    .collect(),
+                data: ix.data.clone(),
+            })
+            .collect();
+
+        self.entries.push(TXHistoryEntry {
+            name: self.next_name.take(),
+            signature: metadata.signature,
+            clock,
+            instructions,
+            accounts: account_keys
+                .iter()
+                .map(|key| (*key, address_book.get_label(key)))
+                .collect(),
+            result,
+            compute_units_consumed: metadata.compute_units_consumed,
+            logs: metadata.logs.clone(),
+        });
+    }
+}
+
+impl TestSVM {
+    /// Names the next transaction executed, so it can be found in the history.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use testsvm_core::prelude::*;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut env = TestSVM::init()?;
+    /// let alice = env.new_wallet("alice")?;
+    /// let bob = env.new_wallet("bob")?;
+    ///
+    /// env.name_next_transaction("pay bob");
+    /// let transfer_ix =
+    ///     solana_sdk::system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), 1_000);
+    /// env.execute_ixs_with_signers(&[transfer_ix], &[&alice])?;
+    ///
+    /// let entry = env.history.find("pay bob").unwrap();
+    /// assert!(entry.is_success());
+    /// assert!(entry.accounts.contains(&(bob.pubkey(), "wallet:bob".to_string())));
+    ///
+    /// env.print_history();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn name_next_transaction(&mut self, name: &str) {
+        self.history.next_name = Some(name.to_string());
+    }
+
+    /// Print every transaction executed so far, formatted using the address book.
+    pub fn print_history(&self) {
+        self.history.print(&self.address_book);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TestSVM;
+    use anyhow::Result;
+    use solana_sdk::{
+        message::{AddressLookupTableAccount, VersionedMessage, v0},
+        pubkey::Pubkey,
+        signature::Signer,
+        system_instruction,
+        transaction::{TransactionError, VersionedTransaction},
+    };
+
+    #[test]
+    fn test_history_records_successes_and_failures() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let bob = env.new_wallet("bob")?;
+
+        env.name_next_transaction("small transfer");
+        env.execute_ixs_with_signers(
+            &[system_instruction::transfer(
+                &alice.pubkey(),
+                &bob.pubkey(),
+                1,
+            )],
+            &[&alice],
+        )?;
+        env.advance_slots(10);
+        // Alice cannot afford this transfer
+        env.name_next_transaction("large transfer");
+        let err = env
+            .execute_ixs_with_signers(
+                &[system_instruction::transfer(
+                    &alice.pubkey(),
+                    &bob.pubkey(),
+                    100_000_000_000,
+                )],
+                &[&alice],
+            )
+            .unwrap_err();
+        // Names are only applied to a single transaction
+        env.execute_ixs_with_signers(
+            &[system_instruction::transfer(
+                &alice.pubkey(),
+                &bob.pubkey(),
+                2,
+            )],
+            &[&alice],
+        )?;
+
+        assert_eq!(env.history.len(), 3);
+        let small = env.history.find("small transfer").unwrap();
+        assert!(small.is_success());
+        assert_eq!(small.instructions[0].accounts[1].pubkey, bob.pubkey());
+        assert!(small.instructions[0].accounts[0].is_signer);
+
+        let large = env.history.find("large transfer").unwrap();
+        assert_eq!(large.result, Err(err.metadata.err.clone()));
+        assert_eq!(large.slot(), small.slot() + 10);
+        assert!(!large.logs.is_empty());
+        assert_eq!(env.history.failures().count(), 1);
+        assert_eq!(env.history.last().unwrap().name, None);
+
+        env.print_history();
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_records_unknown_lookup_table() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let bob = env.new_wallet("bob")?;
+
+        // The lookup table was never created
+        let message = v0::Message::try_compile(
+            &env.default_fee_payer(),
+            &[system_instruction::transfer(
+                &alice.pubkey(),
+                &bob.pubkey(),
+                1,
+            )],
+            &[AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![bob.pubkey()],
+            }],
+            env.svm.latest_blockhash(),
+        )?;
+        let transaction = VersionedTransaction::try_new(
+            VersionedMessage::V0(message),
+            &[&env.default_fee_payer, &alice],
+        )?;
+        let err = env.execute_transaction(transaction).unwrap_err();
+        err.print_error();
+
+        assert_eq!(
+            err.metadata.err,
+            TransactionError::AddressLookupTableNotFound
+        );
+        let entry = env.history.last().unwrap();
+        assert_eq!(entry.result, Err(err.metadata.err.clone()));
+        assert_eq!(entry.instructions[0].accounts[0].pubkey, alice.pubkey());
+        assert_eq!(entry.instructions[0].accounts[1].pubkey, Pubkey::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_is_restored_with_snapshot() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let snapshot = env.snapshot();
+
+        env.execute_ixs_with_signers(
+            &[system_instruction::transfer(
+                &alice.pubkey(),
+                &env.default_fee_payer(),
+                1,
+            )],
+            &[&alice],
+        )?;
+        assert_eq!(env.history.len(), 1);
+
+        env.restore(&snapshot);
+        assert!(env.history.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+//! # Invocation Trees
+//!
+//! Structured program invocations parsed from transaction logs.
+//!
+//! The runtime logs every program invocation, including cross-program invocations (CPIs),
+//! as a flat list of lines. An [InvocationTree] reconstructs the call hierarchy from those
+//! lines, so that nested CPI chains can be printed readably and queried in tests.
+//!
+//! ## Features
+//!
+//! - **Call Hierarchy**: Every invocation with its nested CPIs
+//! - **Per-Invocation Details**: Depth, top-level instruction index, own log lines,
+//!   compute units, result and return data
+//! - **Address Resolution**: Program ids and addresses in logs are labeled with an [AddressBook]
+
+use std::str::FromStr;
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use colored::Colorize;
+use solana_address_book::AddressBook;
+use solana_sdk::pubkey::Pubkey;
+
+/// Error of invocations that never logged a result, e.g. because logs were truncated.
+const NO_RESULT_ERROR: &str = "no result logged (log truncated)";
+
+/// A single program invocation, along with the invocations it made.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Invocation {
+    /// Program that was invoked
+    pub program_id: Pubkey,
+    /// Invocation depth, starting at 1 for top-level instructions
+    pub depth: usize,
+    /// Index of the top-level instruction this invocation belongs to
+    pub instruction_index: usize,
+    /// Log lines emitted by this invocation itself, excluding those of its CPIs
+    pub logs: Vec<String>,
+    /// Compute units consumed by this invocation, including its CPIs
+    pub compute_units_consumed: Option<u64>,
+    /// Compute units that were available to this invocation
+    pub compute_units_limit: Option<u64>,
+    /// `None` if the invocation succeeded, or the failure message.
+    ///
+    /// Invocations without a result line (e.g. because logs were truncated) fail with
+    /// `no result logged (log truncated)`.
+    pub error: Option<String>,
+    /// Data returned by the invocation with `set_return_data`
+    pub return_data: Option<Vec<u8>>,
+    /// Invocations made by this invocation, in order
+    pub children: Vec<Invocation>,
+}
+
+impl Invocation {
+    fn new(program_id: Pubkey, depth: usize, instruction_index: usize) -> Self {
+        Self {
+            program_id,
+            depth,
+            instruction_index,
+            logs: Vec::new(),
+            compute_units_consumed: None,
+            compute_units_limit: None,
+            error: None,
+            return_data: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns whether the invocation succeeded.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Returns this invocation and all of its descendants, in execution order.
+    pub fn iter(&self) -> impl Iterator<Item = &Invocation> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let invocation = stack.pop()?;
+            stack.extend(invocation.children.iter().rev());
+            Some(invocation)
+        })
+    }
+
+    fn print(&self, address_book: &AddressBook, indent: &str, marker: &str) {
+        let status = match &self.error {
+            None => "✅".to_string(),
+            Some(error) => format!("❌ {}", error.bright_red()),
+        };
+        let compute_units = match (self.compute_units_consumed, self.compute_units_limit) {
+            (Some(consumed), Some(limit)) => {
+                format!(" {}", format!("{consumed}/{limit} CU").cyan())
+            }
+            _ => String::new(),
+        };
+        println!(
+            "{}{}{} {}{}",
+            indent,
+            marker,
+            address_book.format_address(&self.program_id),
+            status,
+            compute_units
+        );
+
+        let child_indent = format!("{indent}   ");
+        for log in &self.logs {
+            println!(
+                "{}{}",
+                child_indent,
+                address_book.replace_addresses_in_text(log).dimmed()
+            );
+        }
+        if let Some(return_data) = &self.return_data {
+            println!(
+                "{}{} {}",
+                child_indent,
+                "Return data:".dimmed(),
+                BASE64.encode(return_data)
+            );
+        }
+        for child in &self.children {
+            child.print(address_book, &child_indent, "└─ ");
+        }
+    }
+}
+
+/// The program invocations of a transaction, reconstructed from its logs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InvocationTree {
+    /// Top-level invocations, one per executed instruction
+    pub roots: Vec<Invocation>,
+    /// Log lines emitted outside of any invocation, e.g. `Log truncated`
+    pub logs: Vec<String>,
+    /// Whether some invocations never logged a result, so the tree may be missing invocations
+    pub incomplete: bool,
+}
+
+impl InvocationTree {
+    /// Parses transaction logs into an invocation tree.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use testsvm_core::InvocationTree;
+    ///
+    /// let logs = [
+    ///     "Program 11111111111111111111111111111111 invoke [1]",
+    ///     "Program 11111111111111111111111111111111 success",
+    /// ]
+    /// .map(String::from);
+    ///
+    /// let tree = InvocationTree::parse(&logs);
+    /// assert_eq!(tree.roots.len(), 1);
+    /// assert!(tree.roots[0].is_success());
+    /// ```
+    pub fn parse(logs: &[String]) -> Self {
+        let mut roots: Vec<Invocation> = Vec::new();
+        let mut stack: Vec<Invocation> = Vec::new();
+        let mut outside_logs = Vec::new();
+
+        for line in logs {
+            if let Some((program_id, depth)) = parse_invoke(line) {
+                let instruction_index = match stack.first() {
+                    Some(root) => root.instruction_index,
+                    None => roots.len(),
+                };
+                stack.push(Invocation::new(program_id, depth, instruction_index));
+                continue;
+            }
+
+            let Some(current) = stack.last_mut() else {
+                outside_logs.push(line.clone());
+                continue;
+            };
+            if let Some((consumed, limit)) = parse_consumed(line, &current.program_id) {
+                current.compute_units_consumed = Some(consumed);
+                current.compute_units_limit = Some(limit);
+            } else if let Some(return_data) = parse_return(line, &current.program_id) {
+                current.return_data = Some(return_data);
+            } else if line == &format!("Program {} success", current.program_id) {
+                finish(&mut stack, &mut roots);
+            } else if let Some(error) =
+                line.strip_prefix(&format!("Program {} failed: ", current.program_id))
+            {
+                current.error = Some(error.to_string());
+                finish(&mut stack, &mut roots);
+            } else {
+                current.logs.push(line.clone());
+            }
+        }
+
+        // Invocations that were never closed, e.g. because logs were truncated
+        let incomplete = !stack.is_empty();
+        while let Some(current) = stack.last_mut() {
+            current.error = Some(NO_RESULT_ERROR.to_string());
+            finish(&mut stack, &mut roots);
+        }
+
+        Self {
+            roots,
+            logs: outside_logs,
+            incomplete,
+        }
+    }
+
+    /// Returns all invocations, in execution order.
+    pub fn iter(&self) -> impl Iterator<Item = &Invocation> {
+        self.roots.iter().flat_map(|root| root.iter())
+    }
+
+    /// Returns all invocations of the given program, in execution order.
+    pub fn invocations_of<'a>(
+        &'a self,
+        program_id: &'a Pubkey,
+    ) -> impl Iterator<Item = &'a Invocation> {
+        self.iter()
+            .filter(move |invocation| invocation.program_id == *program_id)
+    }
+
+    /// Returns the deepest failed invocation, which is where the failure originated.
+    pub fn failed_invocation(&self) -> Option<&Invocation> {
+        self.iter()
+            .filter(|invocation| !invocation.is_success())
+            .max_by_key(|invocation| invocation.depth)
+    }
+
+    /// Print the tree, formatted using an [AddressBook].
+    pub fn print(&self, address_book: &AddressBook) {
+        for root in &self.roots {
+            root.print(
+                address_book,
+                "   ",
+                &format!("{} ", format!("#{}", root.instruction_index).bold()),
+            );
+        }
+        for log in &self.logs {
+            println!(
+                "   {}",
+                address_book.replace_addresses_in_text(log).dimmed()
+            );
+        }
+    }
+}
+
+/// Pops the current invocation off the stack and attaches it to its parent.
+fn finish(stack: &mut Vec<Invocation>, roots: &mut Vec<Invocation>) {
+    let Some(invocation) = stack.pop() else {
+        return;
+    };
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(invocation),
+        None => roots.push(invocation),
+    }
+}
+
+/// Parses `Program <id> invoke [<depth>]`.
+fn parse_invoke(line: &str) -> Option<(Pubkey, usize)> {
+    let rest = line.strip_prefix("Program ")?;
+    let (program_id, rest) = rest.split_once(" invoke [")?;
+    let depth = rest.strip_suffix(']')?.parse().ok()?;
+    Some((Pubkey::from_str(program_id).ok()?, depth))
+}
+
+/// Parses `Program <id> consumed <consumed> of <limit> compute units`.
+fn parse_consumed(line: &str, program_id: &Pubkey) -> Option<(u64, u64)> {
+    let rest = line.strip_prefix(&format!("Program {program_id} consumed "))?;
+    let (consumed, rest) = rest.split_once(" of ")?;
+    let limit = rest.strip_suffix(" compute units")?;
+    Some((consumed.parse().ok()?, limit.parse().ok()?))
+}
+
+/// Parses `Program return: <id> <base64 data>`.
+fn parse_return(line: &str, program_id: &Pubkey) -> Option<Vec<u8>> {
+    let data = line.strip_prefix(&format!("Program return: {program_id} "))?;
+    BASE64.decode(data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nested_invocations() {
+        let outer = Pubkey::new_unique();
+        let inner = Pubkey::new_unique();
+        let logs = [
+            format!("Program {outer} invoke [1]"),
+            "Program log: Instruction: Claim".to_string(),
+            format!("Program {inner} invoke [2]"),
+            "Program log: Instruction: MintTo".to_string(),
+            format!("Program {inner} consumed 4500 of 190000 compute units"),
+            format!("Program return: {inner} AQID"),
+            format!("Program {inner} success"),
+            "Program log: done".to_string(),
+            format!("Program {outer} consumed 15000 of 200000 compute units"),
+            format!("Program {outer} success"),
+            format!("Program {inner} invoke [1]"),
+            format!("Program {inner} consumed 100 of 185000 compute units"),
+            format!("Program {inner} failed: custom program error: 0x1"),
+        ];
+
+        let tree = InvocationTree::parse(&logs);
+        assert_eq!(tree.roots.len(), 2);
+
+        let claim = &tree.roots[0];
+        assert_eq!(claim.program_id, outer);
+        assert_eq!(
+            claim.logs,
+            vec!["Program log: Instruction: Claim", "Program log: done"]
+        );
+        assert_eq!(claim.compute_units_consumed, Some(15000));
+        assert_eq!(claim.compute_units_limit, Some(200000));
+
+        let mint = &claim.children[0];
+        assert_eq!(mint.depth, 2);
+        assert_eq!(mint.instruction_index, 0);
+        assert_eq!(mint.return_data, Some(vec![1, 2, 3]));
+        assert!(mint.is_success());
+
+        let failed = tree.failed_invocation().unwrap();
+        assert_eq!(failed.instruction_index, 1);
+        assert_eq!(failed.error.as_deref(), Some("custom program error: 0x1"));
+        assert_eq!(tree.invocations_of(&inner).count(), 2);
+        assert_eq!(tree.iter().count(), 3);
+        assert!(!tree.incomplete);
+    }
+
+    #[test]
+    fn test_parse_truncated_logs() {
+        let outer = Pubkey::new_unique();
+        let inner = Pubkey::new_unique();
+        let logs = [
+            format!("Program {outer} invoke [1]"),
+            format!("Program {inner} invoke [2]"),
+            format!("Program {inner} success"),
+            "Program log: Instruction: Claim".to_string(),
+            "Log truncated".to_string(),
+        ];
+
+        let tree = InvocationTree::parse(&logs);
+        assert!(tree.incomplete);
+        assert!(tree.roots[0].children[0].is_success());
+        assert_eq!(tree.roots[0].error.as_deref(), Some(NO_RESULT_ERROR));
+        assert_eq!(tree.failed_invocation(), Some(&tree.roots[0]));
+
+        let tree = InvocationTree::parse(&["Log truncated".to_string()]);
+        assert!(tree.roots.is_empty());
+        assert_eq!(tree.logs, vec!["Log truncated"]);
+    }
+}
@@ -0,0 +1,78 @@
+//! # Deterministic Keypairs
+//!
+//! Keypairs derived from a seed and a label, so that addresses are stable across runs.
+//!
+//! By default, every wallet, mint and base keypair is random, so logs and failure output
+//! differ between runs. When a seed is set with
+//! [TestSVMBuilder::keypair_seed](crate::TestSVMBuilder::keypair_seed), each keypair is
+//! instead derived from the seed and the label it is registered under in the
+//! [AddressBook](crate::AddressBook). The same seed and labels always produce the same
+//! addresses, which makes output reproducible and diffable.
+//!
+//! Labels must be unique within an environment, as the same label always yields the same keypair.
+
+use solana_sdk::{
+    hash::hashv,
+    signature::{Keypair, keypair_from_seed},
+};
+
+use crate::TestSVM;
+
+/// Derives a keypair from a seed and a label.
+///
+/// # Example
+///
+/// ```
+/// use testsvm_core::derive_keypair;
+/// use solana_sdk::signature::Signer;
+///
+/// let alice = derive_keypair(42, "wallet:alice");
+/// assert_eq!(alice.pubkey(), derive_keypair(42, "wallet:alice").pubkey());
+/// assert_ne!(alice.pubkey(), derive_keypair(43, "wallet:alice").pubkey());
+/// ```
+pub fn derive_keypair(seed: u64, label: &str) -> Keypair {
+    let secret = hashv(&[b"testsvm", &seed.to_le_bytes(), label.as_bytes()]);
+    keypair_from_seed(secret.as_ref()).expect("a hash is a valid keypair seed")
+}
+
+impl TestSVM {
+    /// Creates a new keypair for the given label.
+    ///
+    /// The keypair is derived from the label if a keypair seed is set, and random otherwise.
+    pub fn new_keypair(&self, label: &str) -> Keypair {
+        match self.keypair_seed {
+            Some(seed) => derive_keypair(seed, label),
+            None => Keypair::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestSVMBuilder;
+    use anyhow::Result;
+    use solana_sdk::signature::Signer;
+
+    #[test]
+    fn test_seeded_environments_share_addresses() -> Result<()> {
+        let build = || TestSVMBuilder::new().keypair_seed(7).build();
+        let mut first = build()?;
+        let mut second = build()?;
+
+        assert_eq!(first.default_fee_payer(), second.default_fee_payer());
+        assert_eq!(
+            first.new_wallet("alice")?.pubkey(),
+            second.new_wallet("alice")?.pubkey()
+        );
+        assert_ne!(
+            first.new_wallet("bob")?.pubkey(),
+            first.new_keypair("wallet:alice").pubkey()
+        );
+
+        // Unseeded environments are random
+        let unseeded = TestSVM::init()?;
+        assert_ne!(unseeded.default_fee_payer(), first.default_fee_payer());
+        Ok(())
+    }
+}
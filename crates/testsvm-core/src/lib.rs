@@ -6,30 +6,78 @@
 //! with additional functionality for transaction management, account creation,
 //! and enhanced debugging capabilities.
 
-use std::{
-    env,
-    path::{Path, PathBuf},
-};
+use std::{path::Path, sync::Arc};
 
 use anyhow::*;
-use litesvm::LiteSVM;
+use litesvm::{
+    LiteSVM,
+    types::{FailedTransactionMetadata, TransactionMetadata},
+};
 use solana_sdk::{
+    account::Account,
     clock::Clock,
+    message::{AccountKeys, VersionedMessage, v0::LoadedAddresses},
     pubkey::Pubkey,
     signature::{Keypair, Signer},
-    transaction::Transaction,
+    transaction::{Transaction, VersionedTransaction},
 };
 
 pub use solana_address_book::AddressBook;
 
 mod tx_result;
-pub use tx_result::{TXError, TXResult};
+pub use tx_result::{TXError, TXResult, TXSuccess};
+
+mod account_diff;
+pub use account_diff::AccountDiff;
+
+mod events;
+
+mod anchor_error;
+pub use anchor_error::{AnchorErrorInfo, ComparedValues};
+
+mod invocation_tree;
+pub use invocation_tree::{Invocation, InvocationTree};
+
+mod profiler;
+pub use profiler::{ComputeUnitProfile, ComputeUnitStats};
+
+mod balances;
+use balances::AccountBalance;
+pub use balances::{BalanceReport, LamportBalance, TokenBalance};
 
 mod account_ref;
 pub use account_ref::AccountRef;
 
+mod cheats;
+
+mod upgradeable_programs;
+
+mod builder;
+pub use builder::{TestSVMBuilder, TestSVMPlugin};
+pub use solana_compute_budget::compute_budget::ComputeBudget;
+
+mod clock;
+pub use clock::ClockConfig;
+
+mod history;
+pub use history::{TXHistory, TXHistoryEntry};
+
+mod lookup_tables;
+
+mod snapshot;
+pub use snapshot::TestSVMSnapshot;
+
+mod fixtures;
+
+mod fixture_manifest;
+
+mod anchor_workspace;
+
+mod keypairs;
+pub use keypairs::derive_keypair;
+
 mod litesvm_helpers;
-use litesvm_helpers::new_funded_account;
+use litesvm_helpers::airdrop;
 
 pub mod prelude;
 
@@ -39,37 +87,95 @@ pub struct TestSVM {
     pub svm: LiteSVM,
     /// Default fee payer for transactions.
     pub default_fee_payer: Keypair,
-    /// Address book for labeling addresses
-    pub address_book: AddressBook,
+    /// Address book for labeling addresses, shared with transaction results
+    ///
+    /// Use [TestSVM::address_book_mut] to add addresses.
+    pub address_book: Arc<AddressBook>,
+    /// Every transaction executed so far
+    pub history: TXHistory,
+    /// How time passes when the clock is advanced
+    pub clock_config: ClockConfig,
+    /// Whether to print the account changes of every successful transaction
+    pub print_account_diffs: bool,
+    /// Compute units of every executed transaction, if profiling is enabled
+    pub compute_unit_profile: Option<ComputeUnitProfile>,
+    /// Seed from which new keypairs are derived, or `None` for random keypairs
+    pub keypair_seed: Option<u64>,
+}
+
+/// The accounts of a transaction at one point in time, loaded in full only if writable
+struct AccountStates {
+    /// Full state of every writable account
+    writable: Vec<(Pubkey, Option<Account>)>,
+    /// Balances of every account, in account key order
+    balances: Vec<AccountBalance>,
 }
 
 impl TestSVM {
     /// Create a new test SVM with a payer and address book
     pub fn init() -> Result<Self> {
-        let mut svm = LiteSVM::new();
-        let default_fee_payer = new_funded_account(&mut svm, 1000 * 1_000_000_000)?;
-        let mut address_book = AddressBook::new();
-        address_book.add_default_accounts()?;
-
-        // Add the default fee payer to the address book
-        address_book.add_wallet(default_fee_payer.pubkey(), "default_fee_payer".to_string())?;
-
-        Ok(Self {
-            svm,
-            default_fee_payer,
-            address_book,
-        })
+        TestSVMBuilder::new().build()
+    }
+
+    /// Create a [TestSVMBuilder] for configuring a new test SVM
+    pub fn builder() -> TestSVMBuilder {
+        TestSVMBuilder::new()
     }
 
     /// Execute a transaction with the test SVM's payer
-    pub fn execute_transaction(&mut self, transaction: Transaction) -> TXResult {
-        match self.svm.send_transaction(transaction.clone()) {
-            Result::Ok(tx_result) => Result::Ok(tx_result),
-            Err(e) => Err(Box::new(TXError {
-                transaction,
-                metadata: e.clone(),
-                address_book: self.address_book.clone(),
-            })),
+    ///
+    /// The transaction is recorded in the [TXHistory], whether it succeeds or not. On success,
+    /// the returned [TXSuccess] contains a diff of every writable account and a report of
+    /// the SOL and token balances of every account.
+    pub fn execute_transaction(
+        &mut self,
+        transaction: impl Into<VersionedTransaction>,
+    ) -> TXResult {
+        let transaction = transaction.into();
+        // Transactions with unresolvable lookup tables are rejected without loading any accounts
+        let loaded_addresses = self
+            .resolve_loaded_addresses(&transaction.message)
+            .unwrap_or_default();
+        let clock = self.svm.get_sysvar::<Clock>();
+        let pre_accounts = self.load_accounts(&transaction.message, &loaded_addresses, None);
+        let result = self.svm.send_transaction(transaction.clone());
+
+        let (status, metadata) = match &result {
+            Result::Ok(metadata) => (Result::Ok(()), metadata),
+            Err(e) => (Err(e.err.clone()), &e.meta),
+        };
+        self.history.record(
+            &transaction,
+            &loaded_addresses,
+            clock,
+            status,
+            metadata,
+            &self.address_book,
+        );
+        if let Some(profile) = &mut self.compute_unit_profile {
+            profile.record(&InvocationTree::parse(&metadata.logs), &self.address_book);
+        }
+
+        match result {
+            Result::Ok(metadata) => {
+                let post_accounts = self.load_accounts(
+                    &transaction.message,
+                    &loaded_addresses,
+                    Some(&pre_accounts),
+                );
+                let success = self.tx_success(
+                    transaction,
+                    loaded_addresses,
+                    metadata,
+                    pre_accounts,
+                    post_accounts,
+                );
+                if self.print_account_diffs {
+                    success.print_account_diffs();
+                }
+                Result::Ok(success)
+            }
+            Err(e) => Err(self.tx_error(transaction, loaded_addresses, pre_accounts, e)),
         }
     }
 
@@ -87,27 +193,229 @@ impl TestSVM {
         instructions: &[solana_sdk::instruction::Instruction],
         signers: &[&Keypair],
     ) -> TXResult {
+        let transaction = self.build_transaction(instructions, signers);
+        self.execute_transaction(transaction)
+    }
+
+    /// Simulate a transaction without committing any state changes
+    ///
+    /// The returned [TXSuccess] contains the logs, compute units and return data of the
+    /// transaction, along with the diffs and balances its accounts would have afterwards.
+    /// Neither the SVM nor the [TXHistory] is changed.
+    pub fn simulate_transaction(&self, transaction: impl Into<VersionedTransaction>) -> TXResult {
+        let transaction = transaction.into();
+        let loaded_addresses = self
+            .resolve_loaded_addresses(&transaction.message)
+            .unwrap_or_default();
+        let pre_accounts = self.load_accounts(&transaction.message, &loaded_addresses, None);
+        match self.svm.simulate_transaction(transaction.clone()) {
+            Result::Ok(simulation) => {
+                // Only writable accounts are returned by the simulation
+                let mut post_accounts = pre_accounts.writable.clone();
+                for (pubkey, post) in &mut post_accounts {
+                    if let Some((_, account)) = simulation
+                        .post_accounts
+                        .iter()
+                        .find(|(key, _)| key == pubkey)
+                    {
+                        *post = Some(account.clone().into());
+                    }
+                }
+                let post_accounts = self.account_states(
+                    &transaction.message,
+                    &loaded_addresses,
+                    post_accounts,
+                    Some(&pre_accounts),
+                );
+                Result::Ok(self.tx_success(
+                    transaction,
+                    loaded_addresses,
+                    simulation.meta,
+                    pre_accounts,
+                    post_accounts,
+                ))
+            }
+            Err(e) => Err(self.tx_error(transaction, loaded_addresses, pre_accounts, e)),
+        }
+    }
+
+    /// Simulate instructions with the test SVM's payer
+    pub fn simulate_ixs(&self, instructions: &[solana_sdk::instruction::Instruction]) -> TXResult {
+        self.simulate_ixs_with_signers(instructions, &[])
+    }
+
+    /// Simulate instructions with additional signers
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use testsvm_core::prelude::*;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut env = TestSVM::init()?;
+    /// let alice = env.new_wallet("alice")?;
+    /// let bob = env.new_wallet("bob")?;
+    ///
+    /// let transfer_ix =
+    ///     solana_sdk::system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), 1_000);
+    /// let simulation = env.simulate_ixs_with_signers(&[transfer_ix], &[&alice])?;
+    ///
+    /// // The simulation reports the post-transaction balance of bob's account...
+    /// let bob_balance = simulation.balances.lamports(&bob.pubkey()).unwrap();
+    /// assert_eq!(bob_balance.post, 10_000_001_000);
+    ///
+    /// // ...but the SVM itself is left untouched
+    /// assert_eq!(env.svm.get_balance(&bob.pubkey()), Some(10_000_000_000));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn simulate_ixs_with_signers(
+        &self,
+        instructions: &[solana_sdk::instruction::Instruction],
+        signers: &[&Keypair],
+    ) -> TXResult {
+        let transaction = self.build_transaction(instructions, signers);
+        self.simulate_transaction(transaction)
+    }
+
+    /// Build a [TXSuccess] from the states of the transaction's accounts before and after it
+    fn tx_success(
+        &self,
+        transaction: VersionedTransaction,
+        loaded_addresses: LoadedAddresses,
+        metadata: TransactionMetadata,
+        pre_accounts: AccountStates,
+        post_accounts: AccountStates,
+    ) -> TXSuccess {
+        let balances =
+            BalanceReport::new(&self.svm, &pre_accounts.balances, &post_accounts.balances);
+        let account_diffs = pre_accounts
+            .writable
+            .into_iter()
+            .zip(post_accounts.writable)
+            .map(|((pubkey, pre), (_, post))| AccountDiff::new(pubkey, pre, post))
+            .collect();
+        TXSuccess {
+            balances,
+            transaction,
+            loaded_addresses,
+            metadata,
+            account_diffs,
+            address_book: Arc::clone(&self.address_book),
+        }
+    }
+
+    /// Build a [TXError] for a failed transaction, capturing the current address book
+    ///
+    /// The fee is still charged when a transaction fails, so the balances of its accounts are
+    /// reported from `pre_accounts` to their current state.
+    fn tx_error(
+        &self,
+        transaction: VersionedTransaction,
+        loaded_addresses: LoadedAddresses,
+        pre_accounts: AccountStates,
+        metadata: FailedTransactionMetadata,
+    ) -> Box<TXError> {
+        let post_accounts =
+            self.load_accounts(&transaction.message, &loaded_addresses, Some(&pre_accounts));
+        Box::new(TXError {
+            balances: BalanceReport::new(
+                &self.svm,
+                &pre_accounts.balances,
+                &post_accounts.balances,
+            ),
+            loaded_addresses,
+            transaction,
+            metadata,
+            address_book: Arc::clone(&self.address_book),
+        })
+    }
+
+    /// Get the current state of the accounts of a message
+    ///
+    /// Only writable accounts are loaded in full. Read-only accounts cannot change, so their
+    /// balances are taken from `pre_accounts` if given.
+    fn load_accounts(
+        &self,
+        message: &VersionedMessage,
+        loaded_addresses: &LoadedAddresses,
+        pre_accounts: Option<&AccountStates>,
+    ) -> AccountStates {
+        let writable = AccountKeys::new(message.static_account_keys(), Some(loaded_addresses))
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| lookup_tables::is_writable(message, loaded_addresses, *index))
+            .map(|(_, pubkey)| (*pubkey, self.svm.get_account(pubkey)))
+            .collect();
+        self.account_states(message, loaded_addresses, writable, pre_accounts)
+    }
+
+    /// Collect the balances of every account of a message, given the state of its writable accounts
+    ///
+    /// Balances of read-only accounts are taken from `pre_accounts` if given, and loaded otherwise.
+    fn account_states(
+        &self,
+        message: &VersionedMessage,
+        loaded_addresses: &LoadedAddresses,
+        writable: Vec<(Pubkey, Option<Account>)>,
+        pre_accounts: Option<&AccountStates>,
+    ) -> AccountStates {
+        let mut writable_accounts = writable.iter();
+        let balances = AccountKeys::new(message.static_account_keys(), Some(loaded_addresses))
+            .iter()
+            .enumerate()
+            .map(|(index, pubkey)| {
+                if lookup_tables::is_writable(message, loaded_addresses, index) {
+                    let account = writable_accounts
+                        .next()
+                        .and_then(|(_, account)| account.as_ref());
+                    AccountBalance::new(*pubkey, account)
+                } else if let Some(pre_accounts) = pre_accounts {
+                    pre_accounts.balances[index].clone()
+                } else {
+                    AccountBalance::new(*pubkey, self.svm.get_account(pubkey).as_ref())
+                }
+            })
+            .collect();
+        AccountStates { writable, balances }
+    }
+
+    /// Build a transaction signed by the test SVM's payer and additional signers
+    fn build_transaction(
+        &self,
+        instructions: &[solana_sdk::instruction::Instruction],
+        signers: &[&Keypair],
+    ) -> Transaction {
         let mut all_signers = vec![&self.default_fee_payer];
         all_signers.extend_from_slice(signers);
 
-        let transaction = Transaction::new_signed_with_payer(
+        Transaction::new_signed_with_payer(
             instructions,
             Some(&self.default_fee_payer.pubkey()),
             &all_signers,
             self.svm.latest_blockhash(),
-        );
-
-        self.execute_transaction(transaction)
+        )
     }
 
     /// Create a new funded wallet and add to address book
+    ///
+    /// The wallet's keypair is derived from its label if a keypair seed is set.
     pub fn new_wallet(&mut self, name: &str) -> Result<Keypair> {
-        let keypair = new_funded_account(&mut self.svm, 10 * 1_000_000_000)?; // 10 SOL
         let label = format!("wallet:{name}");
-        self.address_book.add_wallet(keypair.pubkey(), label)?;
+        let keypair = self.new_keypair(&label);
+        airdrop(&mut self.svm, &keypair.pubkey(), 10 * 1_000_000_000)?; // 10 SOL
+        self.address_book_mut()
+            .add_wallet(keypair.pubkey(), label)?;
         Ok(keypair)
     }
 
+    /// Get the address book for adding addresses
+    ///
+    /// The address book is only copied if a transaction result still shares it.
+    pub fn address_book_mut(&mut self) -> &mut AddressBook {
+        Arc::make_mut(&mut self.address_book)
+    }
+
     /// Get the default fee payer's public key
     pub fn default_fee_payer(&self) -> Pubkey {
         self.default_fee_payer.pubkey()
@@ -121,32 +429,27 @@ impl TestSVM {
         path: impl AsRef<Path>,
     ) -> Result<()> {
         self.svm.add_program_from_file(pubkey, path)?;
-        self.address_book.add_program(pubkey, label)
+        self.address_book_mut().add_program(pubkey, label)
     }
 
     /// Add a program fixture from the fixtures directory.
     ///
     /// This method loads a program binary from the fixtures directory. The fixture file
     /// should be located at `fixtures/programs/{fixture_name}.so` relative to your project root.
+    ///
+    /// If `fixtures/manifest.toml` lists the binary, its program id and SHA-256 are checked
+    /// against the manifest.
     pub fn add_program_fixture(&mut self, fixture_name: &str, pubkey: Pubkey) -> Result<()> {
-        let path = env::var("CARGO_MANIFEST_DIR")
-            .map(PathBuf::from)
-            .map_err(|e| anyhow!("Failed to get environment variable `CARGO_MANIFEST_DIR`: {e}"))?
-            .ancestors()
-            .find_map(|ancestor| {
-                let fixtures_dir = ancestor.join("fixtures");
-                fixtures_dir.exists().then_some(fixtures_dir)
-            })
-            .ok_or_else(|| anyhow!("`fixtures` directory not found"))
-            .map(|fixtures_dir| {
-                fixtures_dir
-                    .join("programs")
-                    .join(fixture_name)
-                    .with_extension("so")
-            })?;
+        let path = fixtures::fixtures_dir()?
+            .join("programs")
+            .join(fixture_name)
+            .with_extension("so");
+        let program = std::fs::read(&path)
+            .with_context(|| format!("Failed to read program fixture {}", path.display()))?;
+        fixture_manifest::verify_program_fixture(&path, &pubkey, &program)?;
 
-        self.add_program_from_path(fixture_name, pubkey, &path)?;
-        Ok(())
+        self.svm.add_program(pubkey, &program);
+        self.address_book_mut().add_program(pubkey, fixture_name)
     }
 
     /// Finds a program derived address and return an [AccountRef] with proper type information.
@@ -168,28 +471,8 @@ impl TestSVM {
         program_id: Pubkey,
     ) -> Result<(AccountRef<T>, u8)> {
         let (pubkey, bump) = self
-            .address_book
+            .address_book_mut()
             .find_pda_with_bump(label, seeds, program_id)?;
         Ok((AccountRef::new(pubkey), bump))
     }
-
-    /// Advance the time by the specified number of seconds
-    /// Assumes 450ms per slot, in practice this is not always the case.
-    pub fn advance_time(&mut self, seconds: u64) {
-        let mut clock = self.svm.get_sysvar::<Clock>();
-        clock.unix_timestamp += seconds as i64;
-        // assume 450ms per slot.
-        let num_slots = seconds / 450;
-        clock.slot += num_slots;
-        self.svm.set_sysvar(&clock);
-    }
-
-    /// Advance slots using LiteSVM's warp_to_slot feature
-    /// This is useful for simulating time passing in tests
-    pub fn advance_slots(&mut self, num_slots: u32) {
-        let current_slot = self.svm.get_sysvar::<solana_sdk::clock::Clock>().slot;
-        let target_slot = current_slot + num_slots as u64;
-
-        self.svm.warp_to_slot(target_slot);
-    }
 }
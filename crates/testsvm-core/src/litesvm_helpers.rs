@@ -4,21 +4,17 @@
 
 use anyhow::Result;
 use litesvm::LiteSVM;
-use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::pubkey::Pubkey;
 
-/// Creates a new funded account with the specified amount of lamports
-pub fn new_funded_account(svm: &mut LiteSVM, lamports: u64) -> Result<Keypair> {
-    let keypair = Keypair::new();
-
-    // Add SOL to the new account
-    svm.airdrop(&keypair.pubkey(), lamports).map_err(|e| {
+/// Airdrops the specified amount of lamports to an account
+pub fn airdrop(svm: &mut LiteSVM, pubkey: &Pubkey, lamports: u64) -> Result<()> {
+    svm.airdrop(pubkey, lamports).map_err(|e| {
         anyhow::anyhow!(
             "Failed to airdrop {} lamports to account {}: {:?}",
             lamports,
-            keypair.pubkey(),
+            pubkey,
             e
         )
     })?;
-
-    Ok(keypair)
+    Ok(())
 }
@@ -0,0 +1,308 @@
+//! # Address Lookup Tables
+//!
+//! Versioned (v0) transaction support and address lookup table helpers for TestSVM.
+//!
+//! Lookup tables are created and extended through the address lookup table program,
+//! just like on a real cluster, and are registered in the address book so that
+//! addresses loaded through them are labeled in transaction output.
+//!
+//! LiteSVM does not produce new slots on its own, so the helpers take care of the
+//! slot bookkeeping a validator would normally do: the current slot is recorded in
+//! the `SlotHashes` sysvar before a table is created, and the clock is advanced by
+//! one slot after a table is extended so that the new addresses become active.
+
+use anyhow::*;
+use solana_address_lookup_table_interface::{instruction, state::AddressLookupTable};
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    message::{AddressLookupTableAccount, VersionedMessage, v0},
+    pubkey::Pubkey,
+    signature::Keypair,
+    slot_hashes::SlotHashes,
+    transaction::VersionedTransaction,
+};
+
+use crate::{TXResult, TestSVM};
+
+/// Maximum number of addresses added per extend transaction, keeping each
+/// transaction well below the packet size limit.
+const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+
+impl TestSVM {
+    /// Create an address lookup table containing the given addresses and add it to the address book
+    ///
+    /// The default fee payer is used as the table's authority. The addresses are usable in
+    /// v0 transactions as soon as this returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use testsvm_core::prelude::*;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut env = TestSVM::init()?;
+    /// let alice = env.new_wallet("alice")?;
+    /// let bob = env.new_wallet("bob")?;
+    ///
+    /// let table = env.create_lookup_table("wallets", &[bob.pubkey()])?;
+    ///
+    /// let transfer_ix =
+    ///     solana_sdk::system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), 1_000);
+    /// env.execute_v0_ixs(&[transfer_ix], &[&alice], &[table])??;
+    ///
+    /// assert_eq!(env.svm.get_balance(&bob.pubkey()), Some(10_000_001_000));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_lookup_table(&mut self, label: &str, addresses: &[Pubkey]) -> Result<Pubkey> {
+        let recent_slot = self.svm.get_sysvar::<Clock>().slot;
+        self.record_slot_hash(recent_slot);
+
+        let authority = self.default_fee_payer();
+        let (create_ix, lookup_table) =
+            instruction::create_lookup_table(authority, authority, recent_slot);
+
+        self.address_book_mut().add_custom(
+            lookup_table,
+            label.to_string(),
+            "lookup_table".to_string(),
+        )?;
+
+        self.execute_ixs(&[create_ix])
+            .map_err(|e| anyhow!("Failed to create lookup table: {}", e))?;
+        self.extend_lookup_table(lookup_table, addresses)?;
+
+        Ok(lookup_table)
+    }
+
+    /// Extend an address lookup table with additional addresses
+    ///
+    /// The clock is advanced by one slot afterwards so that the new addresses are active.
+    pub fn extend_lookup_table(
+        &mut self,
+        lookup_table: Pubkey,
+        addresses: &[Pubkey],
+    ) -> Result<()> {
+        let authority = self.default_fee_payer();
+        for chunk in addresses.chunks(MAX_ADDRESSES_PER_EXTEND) {
+            let extend_ix = instruction::extend_lookup_table(
+                lookup_table,
+                authority,
+                Some(authority),
+                chunk.to_vec(),
+            );
+            self.execute_ixs(&[extend_ix])
+                .map_err(|e| anyhow!("Failed to extend lookup table: {}", e))?;
+        }
+
+        self.advance_slots(1);
+        Ok(())
+    }
+
+    /// Load an address lookup table from the SVM
+    pub fn get_lookup_table(&self, lookup_table: &Pubkey) -> Result<AddressLookupTableAccount> {
+        let account = self
+            .svm
+            .get_account(lookup_table)
+            .ok_or_else(|| anyhow!("Lookup table not found: {}", lookup_table))?;
+        let table = AddressLookupTable::deserialize(&account.data)
+            .map_err(|e| anyhow!("Invalid lookup table {}: {}", lookup_table, e))?;
+        Ok(AddressLookupTableAccount {
+            key: *lookup_table,
+            addresses: table.addresses.to_vec(),
+        })
+    }
+
+    /// Execute instructions as a v0 transaction using the given address lookup tables
+    ///
+    /// Unlike the other `execute_*` helpers, this returns a nested result. The lookup tables
+    /// must be loaded to compile the message, so the outer error is returned when there is no
+    /// transaction to execute yet, e.g. because a lookup table does not exist. The inner
+    /// [TXResult] is the outcome of the transaction itself.
+    pub fn execute_v0_ixs(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        lookup_tables: &[Pubkey],
+    ) -> Result<TXResult> {
+        let lookup_table_accounts = lookup_tables
+            .iter()
+            .map(|lookup_table| self.get_lookup_table(lookup_table))
+            .collect::<Result<Vec<_>>>()?;
+
+        let message = v0::Message::try_compile(
+            &self.default_fee_payer(),
+            instructions,
+            &lookup_table_accounts,
+            self.svm.latest_blockhash(),
+        )?;
+
+        let mut all_signers = vec![&self.default_fee_payer];
+        all_signers.extend_from_slice(signers);
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &all_signers)?;
+
+        Ok(self.execute_transaction(transaction))
+    }
+
+    /// Resolve the addresses a message loads through lookup tables
+    ///
+    /// Returns an error if a lookup table does not exist or an index is out of range. The
+    /// runtime rejects such transactions before loading any of their accounts.
+    pub(crate) fn resolve_loaded_addresses(
+        &self,
+        message: &VersionedMessage,
+    ) -> Result<v0::LoadedAddresses> {
+        let mut loaded_addresses = v0::LoadedAddresses::default();
+        for lookup in message.address_table_lookups().unwrap_or_default() {
+            let table = self.get_lookup_table(&lookup.account_key)?;
+            let resolve = |indexes: &[u8]| {
+                indexes
+                    .iter()
+                    .map(|index| {
+                        table
+                            .addresses
+                            .get(*index as usize)
+                            .copied()
+                            .ok_or_else(|| {
+                                anyhow!(
+                                    "Index {} is out of range for lookup table {}",
+                                    index,
+                                    lookup.account_key
+                                )
+                            })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            };
+            loaded_addresses
+                .writable
+                .extend(resolve(&lookup.writable_indexes)?);
+            loaded_addresses
+                .readonly
+                .extend(resolve(&lookup.readonly_indexes)?);
+        }
+        Ok(loaded_addresses)
+    }
+
+    /// Record the current slot in the `SlotHashes` sysvar, as a validator would
+    fn record_slot_hash(&mut self, slot: u64) {
+        let slot_hashes = self.svm.get_sysvar::<SlotHashes>();
+        if slot_hashes.get(&slot).is_none() {
+            let mut entries = slot_hashes.slot_hashes().to_vec();
+            entries.push((slot, self.svm.latest_blockhash()));
+            self.svm.set_sysvar(&SlotHashes::new(&entries));
+        }
+    }
+}
+
+/// Returns whether the account at the given index of a message is writable,
+/// taking addresses loaded from lookup tables into account.
+pub(crate) fn is_writable(
+    message: &VersionedMessage,
+    loaded_addresses: &v0::LoadedAddresses,
+    index: usize,
+) -> bool {
+    let num_static_keys = message.static_account_keys().len();
+    if index < num_static_keys {
+        message.is_maybe_writable(index, None)
+    } else {
+        index - num_static_keys < loaded_addresses.writable.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TestSVM;
+    use anyhow::Result;
+    use solana_sdk::{
+        message::{AddressLookupTableAccount, VersionedMessage, v0},
+        pubkey::Pubkey,
+        signature::Signer,
+        system_instruction,
+    };
+
+    #[test]
+    fn test_multiple_lookup_tables() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let bob = env.new_wallet("bob")?;
+        let carol = env.new_wallet("carol")?;
+
+        let table_a = env.create_lookup_table("table_a", &[bob.pubkey()])?;
+        let table_b = env.create_lookup_table("table_b", &[carol.pubkey()])?;
+        assert_ne!(table_a, table_b);
+        assert_eq!(env.address_book.get_label(&table_b), "table_b");
+
+        env.execute_v0_ixs(
+            &[
+                system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), 1),
+                system_instruction::transfer(&alice.pubkey(), &carol.pubkey(), 2),
+            ],
+            &[&alice],
+            &[table_a, table_b],
+        )??;
+
+        assert_eq!(env.svm.get_balance(&carol.pubkey()), Some(10_000_000_002));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unresolvable_lookup_tables() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let bob = env.new_wallet("bob")?;
+        let carol = env.new_wallet("carol")?;
+        let table = env.create_lookup_table("wallets", &[bob.pubkey()])?;
+
+        let compile = |lookup_table: AddressLookupTableAccount| -> Result<VersionedMessage> {
+            let message = v0::Message::try_compile(
+                &alice.pubkey(),
+                &[system_instruction::transfer(
+                    &alice.pubkey(),
+                    &carol.pubkey(),
+                    1,
+                )],
+                &[lookup_table],
+                env.svm.latest_blockhash(),
+            )?;
+            Ok(VersionedMessage::V0(message))
+        };
+
+        // A table that does not exist
+        let missing_table = compile(AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![carol.pubkey()],
+        })?;
+        assert!(env.resolve_loaded_addresses(&missing_table).is_err());
+
+        // An index past the end of an existing table
+        let out_of_range = compile(AddressLookupTableAccount {
+            key: table,
+            addresses: vec![bob.pubkey(), carol.pubkey()],
+        })?;
+        assert!(env.resolve_loaded_addresses(&out_of_range).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_v0_transaction_resolves_loaded_accounts() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let bob = env.new_wallet("bob")?;
+        let table = env.create_lookup_table("wallets", &[bob.pubkey()])?;
+
+        // Alice cannot afford this transfer
+        let transfer_ix =
+            system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), 100_000_000_000);
+        let err = env
+            .execute_v0_ixs(&[transfer_ix], &[&alice], &[table])?
+            .unwrap_err();
+
+        assert_eq!(err.loaded_addresses.writable, vec![bob.pubkey()]);
+        assert!(err.account_keys().iter().any(|key| *key == bob.pubkey()));
+        err.print_error();
+        Ok(())
+    }
+}
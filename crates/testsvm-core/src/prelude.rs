@@ -4,7 +4,11 @@
 //! and traits from testsvm-core for convenient access.
 
 // Core TestSVM types
-pub use crate::{AccountRef, TXError, TXResult, TestSVM};
+pub use crate::{
+    AccountDiff, AccountRef, AnchorErrorInfo, BalanceReport, ClockConfig, ComparedValues,
+    ComputeUnitProfile, InvocationTree, TXError, TXHistory, TXHistoryEntry, TXResult, TXSuccess,
+    TestSVM, TestSVMBuilder, TestSVMPlugin, TestSVMSnapshot,
+};
 
 // Address book types
 pub use solana_address_book::{AddressBook, AddressRole, RegisteredAddress};
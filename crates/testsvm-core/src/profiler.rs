@@ -0,0 +1,277 @@
+//! # Compute Unit Profiler
+//!
+//! Aggregated compute unit usage across every transaction executed by a [TestSVM].
+//!
+//! When profiling is enabled, the [InvocationTree] of each executed transaction is folded
+//! into a [ComputeUnitProfile], which breaks compute units down by:
+//!
+//! - **Top-level instruction**: Each program and, where it logs `Instruction: X`, the Anchor instruction name
+//! - **CPI callee**: Each program invoked through a cross-program invocation
+//! - **Call stack**: Self compute units of every distinct call stack, in the folded format
+//!   used by flamegraph tools such as `inferno-flamegraph` and `flamegraph.pl`
+//!
+//! ```bash
+//! inferno-flamegraph < target/cu.folded > cu.svg
+//! ```
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::*;
+use colored::Colorize;
+use solana_address_book::AddressBook;
+
+use crate::{Invocation, InvocationTree, TestSVM};
+
+/// Compute unit usage of a group of invocations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComputeUnitStats {
+    /// Number of invocations
+    pub count: u64,
+    /// Total compute units consumed
+    pub total: u64,
+    /// Maximum compute units consumed by a single invocation
+    pub max: u64,
+}
+
+impl ComputeUnitStats {
+    /// Returns the average compute units consumed per invocation.
+    pub fn average(&self) -> u64 {
+        self.total.checked_div(self.count).unwrap_or(0)
+    }
+
+    fn add(&mut self, compute_units: u64) {
+        self.count += 1;
+        self.total += compute_units;
+        self.max = self.max.max(compute_units);
+    }
+}
+
+/// Compute units consumed by the transactions executed while profiling.
+#[derive(Clone, Debug, Default)]
+pub struct ComputeUnitProfile {
+    transactions: u64,
+    instructions: BTreeMap<String, ComputeUnitStats>,
+    cpi_programs: BTreeMap<String, ComputeUnitStats>,
+    folded_stacks: BTreeMap<String, u64>,
+}
+
+impl ComputeUnitProfile {
+    /// Returns the number of profiled transactions.
+    pub fn transactions(&self) -> u64 {
+        self.transactions
+    }
+
+    /// Returns compute units per top-level instruction, keyed by `program` or `program::Instruction`.
+    pub fn instructions(&self) -> &BTreeMap<String, ComputeUnitStats> {
+        &self.instructions
+    }
+
+    /// Returns compute units per program invoked through CPI, keyed by program label.
+    pub fn cpi_programs(&self) -> &BTreeMap<String, ComputeUnitStats> {
+        &self.cpi_programs
+    }
+
+    /// Returns the self compute units of each call stack, keyed by `;`-separated frames.
+    ///
+    /// `;` and whitespace in frame names are replaced with `_`.
+    pub fn folded_stacks(&self) -> &BTreeMap<String, u64> {
+        &self.folded_stacks
+    }
+
+    /// Returns the total compute units consumed by all top-level instructions.
+    pub fn total_compute_units(&self) -> u64 {
+        self.instructions.values().map(|stats| stats.total).sum()
+    }
+
+    /// Adds the invocations of a transaction to the profile.
+    pub fn record(&mut self, invocation_tree: &InvocationTree, address_book: &AddressBook) {
+        self.transactions += 1;
+        for root in &invocation_tree.roots {
+            let frame = frame_name(root, address_book);
+            self.instructions
+                .entry(frame.clone())
+                .or_default()
+                .add(root.compute_units_consumed.unwrap_or(0));
+            self.record_stack(root, folded_frame_name(root, address_book), address_book);
+        }
+    }
+
+    fn record_stack(&mut self, invocation: &Invocation, stack: String, address_book: &AddressBook) {
+        let consumed = invocation.compute_units_consumed.unwrap_or(0);
+        let mut children_consumed = 0;
+        for child in &invocation.children {
+            let child_consumed = child.compute_units_consumed.unwrap_or(0);
+            children_consumed += child_consumed;
+            self.cpi_programs
+                .entry(address_book.get_label(&child.program_id))
+                .or_default()
+                .add(child_consumed);
+            let frame = folded_frame_name(child, address_book);
+            self.record_stack(child, format!("{stack};{frame}"), address_book);
+        }
+        *self.folded_stacks.entry(stack).or_default() += consumed.saturating_sub(children_consumed);
+    }
+
+    /// Print a summary table of the profile.
+    pub fn print(&self) {
+        println!("\n{}", "═".repeat(80).dimmed());
+        println!(
+            "⚡ {} ({} transactions, {} CU total):",
+            "Compute Unit Profile".bold(),
+            self.transactions,
+            self.total_compute_units()
+        );
+        print_table("Top-level instructions", &self.instructions);
+        print_table("CPI callees", &self.cpi_programs);
+        println!("{}", "═".repeat(80).dimmed());
+    }
+
+    /// Write the call stacks in folded format, one `frame;frame;frame count` line per stack.
+    pub fn write_folded_stacks(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let folded = self
+            .folded_stacks
+            .iter()
+            .map(|(stack, compute_units)| format!("{stack} {compute_units}\n"))
+            .collect::<String>();
+        fs::write(path, folded)
+            .with_context(|| format!("Failed to write folded stacks to {}", path.display()))
+    }
+}
+
+/// Returns the frame name of an invocation, e.g. `quarry_mine::ClaimRewardsV2`.
+fn frame_name(invocation: &Invocation, address_book: &AddressBook) -> String {
+    let program = address_book.get_label(&invocation.program_id);
+    let instruction = invocation
+        .logs
+        .iter()
+        .find_map(|log| log.strip_prefix("Program log: Instruction: "));
+    match instruction {
+        Some(instruction) => format!("{program}::{instruction}"),
+        None => program,
+    }
+}
+
+/// Returns the frame name of an invocation in a folded stack.
+///
+/// Labels may contain `;`, which separates frames, or whitespace, which separates a stack from
+/// its count, so both are replaced with `_`.
+fn folded_frame_name(invocation: &Invocation, address_book: &AddressBook) -> String {
+    frame_name(invocation, address_book).replace(|c: char| c == ';' || c.is_whitespace(), "_")
+}
+
+fn print_table(title: &str, rows: &BTreeMap<String, ComputeUnitStats>) {
+    println!("{}", "─".repeat(80).dimmed());
+    println!(
+        "{:<44} {:>7} {:>9} {:>9} {:>9}",
+        title.bold(),
+        "calls",
+        "avg",
+        "max",
+        "total"
+    );
+    let mut rows = rows.iter().collect::<Vec<_>>();
+    rows.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total));
+    for (name, stats) in rows {
+        println!(
+            "{:<44} {:>7} {:>9} {:>9} {:>9}",
+            name,
+            stats.count,
+            stats.average(),
+            stats.max,
+            stats.total.to_string().cyan()
+        );
+    }
+}
+
+impl TestSVM {
+    /// Start recording the compute units of every executed transaction.
+    ///
+    /// Does nothing if profiling is already enabled.
+    pub fn enable_compute_unit_profiling(&mut self) {
+        self.compute_unit_profile
+            .get_or_insert_with(Default::default);
+    }
+
+    /// Print a summary of the compute units recorded while profiling.
+    pub fn print_compute_unit_profile(&self) {
+        match &self.compute_unit_profile {
+            Some(profile) => profile.print(),
+            None => println!("⚡ Compute unit profiling is not enabled"),
+        }
+    }
+
+    /// Write the recorded call stacks in folded format for flamegraph tools.
+    ///
+    /// Returns an error if profiling is not enabled.
+    pub fn write_compute_unit_profile(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.compute_unit_profile
+            .as_ref()
+            .ok_or_else(|| anyhow!("Compute unit profiling is not enabled"))?
+            .write_folded_stacks(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_profile_breaks_down_invocations() {
+        let outer = Pubkey::new_unique();
+        let inner = Pubkey::new_unique();
+        let mut address_book = AddressBook::new();
+        address_book.add_program(outer, "outer").unwrap();
+        address_book.add_program(inner, "inner").unwrap();
+
+        let logs = [
+            format!("Program {outer} invoke [1]"),
+            "Program log: Instruction: Claim".to_string(),
+            format!("Program {inner} invoke [2]"),
+            "Program log: Instruction: MintTo".to_string(),
+            format!("Program {inner} consumed 4000 of 190000 compute units"),
+            format!("Program {inner} success"),
+            format!("Program {outer} consumed 10000 of 200000 compute units"),
+            format!("Program {outer} success"),
+        ];
+        let mut profile = ComputeUnitProfile::default();
+        profile.record(&InvocationTree::parse(&logs), &address_book);
+        profile.record(&InvocationTree::parse(&logs), &address_book);
+
+        let claim = profile.instructions()["outer::Claim"];
+        assert_eq!(claim.count, 2);
+        assert_eq!(claim.average(), 10000);
+        assert_eq!(profile.cpi_programs()["inner"].total, 8000);
+        assert_eq!(profile.folded_stacks()["outer::Claim"], 12000);
+        assert_eq!(profile.folded_stacks()["outer::Claim;inner::MintTo"], 8000);
+        assert_eq!(profile.total_compute_units(), 20000);
+        profile.print();
+    }
+
+    #[test]
+    fn test_folded_stacks_escape_labels() {
+        let outer = Pubkey::new_unique();
+        let inner = Pubkey::new_unique();
+        let mut address_book = AddressBook::new();
+        address_book.add_program(outer, "my program").unwrap();
+        address_book.add_program(inner, "token;v2").unwrap();
+
+        let logs = [
+            format!("Program {outer} invoke [1]"),
+            format!("Program {inner} invoke [2]"),
+            format!("Program {inner} consumed 4000 of 190000 compute units"),
+            format!("Program {inner} success"),
+            format!("Program {outer} consumed 10000 of 200000 compute units"),
+            format!("Program {outer} success"),
+        ];
+        let mut profile = ComputeUnitProfile::default();
+        profile.record(&InvocationTree::parse(&logs), &address_book);
+
+        assert_eq!(profile.folded_stacks()["my_program"], 6000);
+        assert_eq!(profile.folded_stacks()["my_program;token_v2"], 4000);
+        // Tables are not in folded format, so they keep the labels as is
+        assert_eq!(profile.instructions()["my program"].total, 10000);
+        assert_eq!(profile.cpi_programs()["token;v2"].total, 4000);
+    }
+}
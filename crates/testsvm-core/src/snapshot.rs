@@ -0,0 +1,102 @@
+//! # State Snapshots
+//!
+//! Point-in-time copies of a [TestSVM] environment.
+//!
+//! A snapshot captures the full LiteSVM state (accounts, sysvars, blockhash and
+//! loaded programs) together with the [AddressBook] and transaction history. This
+//! allows an expensive setup to be performed once and then restored before each
+//! scenario, letting a single test branch into many independent cases.
+
+use std::sync::Arc;
+
+use litesvm::LiteSVM;
+use solana_address_book::AddressBook;
+
+use crate::{TXHistory, TestSVM};
+
+/// A saved copy of the state of a [TestSVM].
+///
+/// Created with [TestSVM::snapshot] and applied with [TestSVM::restore].
+/// A snapshot may be restored any number of times.
+#[derive(Clone)]
+pub struct TestSVMSnapshot {
+    /// LiteSVM state at the time of the snapshot
+    svm: LiteSVM,
+    /// Address book at the time of the snapshot
+    address_book: Arc<AddressBook>,
+    /// Transaction history at the time of the snapshot
+    history: TXHistory,
+}
+
+impl TestSVM {
+    /// Captures the current state of the environment.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use testsvm_core::prelude::*;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut env = TestSVM::init()?;
+    /// let alice = env.new_wallet("alice")?;
+    /// let snapshot = env.snapshot();
+    ///
+    /// // Branch: create another wallet
+    /// let bob = env.new_wallet("bob")?;
+    /// assert!(env.svm.get_account(&bob.pubkey()).is_some());
+    ///
+    /// // Rewind to the snapshot
+    /// env.restore(&snapshot);
+    /// assert!(env.svm.get_account(&alice.pubkey()).is_some());
+    /// assert!(env.svm.get_account(&bob.pubkey()).is_none());
+    /// assert!(!env.address_book.contains(&bob.pubkey()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn snapshot(&self) -> TestSVMSnapshot {
+        TestSVMSnapshot {
+            svm: self.svm.clone(),
+            address_book: Arc::clone(&self.address_book),
+            history: self.history.clone(),
+        }
+    }
+
+    /// Restores the environment to a previously captured snapshot.
+    ///
+    /// The default fee payer is kept as-is, since it is part of the environment's
+    /// configuration rather than its state.
+    pub fn restore(&mut self, snapshot: &TestSVMSnapshot) {
+        self.svm = snapshot.svm.clone();
+        self.address_book = snapshot.address_book.clone();
+        self.history = snapshot.history.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TestSVM;
+    use anyhow::Result;
+    use solana_sdk::{clock::Clock, signature::Signer, system_instruction};
+
+    #[test]
+    fn test_restore_reverts_accounts_and_clock() -> Result<()> {
+        let mut env = TestSVM::init()?;
+        let alice = env.new_wallet("alice")?;
+        let bob = env.new_wallet("bob")?;
+        let snapshot = env.snapshot();
+
+        let transfer_ix = system_instruction::transfer(&alice.pubkey(), &bob.pubkey(), 1_000);
+        env.execute_ixs_with_signers(std::slice::from_ref(&transfer_ix), &[&alice])?;
+        env.advance_time(3600);
+        let balance_after = env.svm.get_balance(&bob.pubkey());
+
+        env.restore(&snapshot);
+        assert_eq!(env.svm.get_balance(&bob.pubkey()), Some(10 * 1_000_000_000));
+        assert_eq!(env.svm.get_sysvar::<Clock>().unix_timestamp, 0);
+
+        // The same transaction can be replayed on the restored branch
+        env.execute_ixs_with_signers(&[transfer_ix], &[&alice])?;
+        assert_eq!(env.svm.get_balance(&bob.pubkey()), balance_after);
+        Ok(())
+    }
+}
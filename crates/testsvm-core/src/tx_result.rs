@@ -17,13 +17,23 @@
 
 use std::error::Error;
 use std::fmt::Display;
+use std::ops::Deref;
+use std::sync::Arc;
 
 use colored::Colorize;
 use litesvm::types::{FailedTransactionMetadata, TransactionMetadata};
-use solana_sdk::transaction::Transaction;
+use solana_sdk::{
+    message::{AccountKeys, v0::LoadedAddresses},
+    pubkey::Pubkey,
+    transaction::VersionedTransaction,
+};
 
 use solana_address_book::AddressBook;
 
+use crate::{
+    AccountDiff, AnchorErrorInfo, BalanceReport, InvocationTree, lookup_tables::is_writable,
+};
+
 /// Error type representing a failed transaction with detailed metadata.
 ///
 /// Contains both the original transaction and the failure metadata from LiteSVM,
@@ -31,11 +41,15 @@ use solana_address_book::AddressBook;
 #[derive(Debug)]
 pub struct TXError {
     /// The transaction that failed
-    pub transaction: Transaction,
+    pub transaction: VersionedTransaction,
+    /// Addresses loaded from address lookup tables by the transaction
+    pub loaded_addresses: LoadedAddresses,
     /// Underlying failed transaction metadata
     pub metadata: FailedTransactionMetadata,
+    /// SOL and token balances of the transaction's accounts, which only change by the fee
+    pub balances: BalanceReport,
     /// Address book at the time of transaction failure
-    pub address_book: AddressBook,
+    pub address_book: Arc<AddressBook>,
 }
 
 impl Error for TXError {}
@@ -47,6 +61,29 @@ impl Display for TXError {
 }
 
 impl TXError {
+    /// Returns all account keys of the transaction, including those loaded from lookup tables.
+    pub fn account_keys(&self) -> AccountKeys<'_> {
+        AccountKeys::new(
+            self.transaction.message.static_account_keys(),
+            Some(&self.loaded_addresses),
+        )
+    }
+
+    /// Returns the program invocations of the transaction, parsed from its logs.
+    pub fn invocation_tree(&self) -> InvocationTree {
+        InvocationTree::parse(&self.metadata.meta.logs)
+    }
+
+    /// Returns the Anchor error logged by the failing program, if any.
+    pub fn anchor_error(&self) -> Option<AnchorErrorInfo> {
+        AnchorErrorInfo::parse(&self.metadata.meta.logs)
+    }
+
+    /// Returns whether the account at the given index is writable.
+    fn is_writable(&self, index: usize) -> bool {
+        is_writable(&self.transaction.message, &self.loaded_addresses, index)
+    }
+
     /// Print the error details, formatted using an [AddressBook].
     pub fn print_error(&self) {
         println!(
@@ -59,13 +96,26 @@ impl TXError {
         println!(
             "\n{} {}",
             "📜".yellow(),
-            "Transaction Logs:".yellow().bold()
-        );
-        println!(
-            "{}",
-            self.address_book
-                .replace_addresses_in_text(&self.metadata.meta.pretty_logs())
+            "Program Invocations:".yellow().bold()
         );
+        let invocation_tree = self.invocation_tree();
+        invocation_tree.print(&self.address_book);
+        // The tree may be missing invocations, so show the raw logs as well
+        if invocation_tree.incomplete {
+            println!(
+                "\n{} {}",
+                "📜".yellow(),
+                "Transaction Logs:".yellow().bold()
+            );
+            println!(
+                "{}",
+                self.address_book
+                    .replace_addresses_in_text(&self.metadata.meta.pretty_logs())
+            );
+        }
+        if let Some(anchor_error) = self.anchor_error() {
+            anchor_error.print(&self.address_book);
+        }
 
         // Log each instruction for debugging
         println!(
@@ -73,13 +123,19 @@ impl TXError {
             "📋".blue(),
             "Instructions in failed transaction:".blue().bold()
         );
-        for (i, ix) in self.transaction.message.instructions.iter().enumerate() {
-            let program_id = self.transaction.message.account_keys[ix.program_id_index as usize];
+        let account_keys = self.account_keys();
+        let num_static_keys = self.transaction.message.static_account_keys().len();
+        // Accounts from lookup tables that could not be resolved have no key
+        let format_account = |index: usize| match account_keys.get(index) {
+            Some(key) => self.address_book.format_address(key),
+            None => format!("<unresolved account {index}>").red().to_string(),
+        };
+        for (i, ix) in self.transaction.message.instructions().iter().enumerate() {
             println!(
                 "   {} {}: {}",
                 "Instruction".dimmed(),
                 i.to_string().bold(),
-                self.address_book.format_address(&program_id)
+                format_account(ix.program_id_index as usize)
             );
             println!(
                 "   {} {}",
@@ -89,12 +145,9 @@ impl TXError {
 
             // Show account details with labels from address book
             for (j, account_index) in ix.accounts.iter().enumerate() {
-                let account_key = self.transaction.message.account_keys[*account_index as usize];
-                let is_signer = self.transaction.message.is_signer(*account_index as usize);
-                let is_writable = self
-                    .transaction
-                    .message
-                    .is_maybe_writable(*account_index as usize, None);
+                let account_index = *account_index as usize;
+                let is_signer = self.transaction.message.is_signer(account_index);
+                let is_writable = self.is_writable(account_index);
 
                 let mut flags = Vec::new();
                 if is_signer {
@@ -103,6 +156,9 @@ impl TXError {
                 if is_writable {
                     flags.push("writable".yellow().to_string());
                 }
+                if account_index >= num_static_keys {
+                    flags.push("lookup".magenta().to_string());
+                }
 
                 let flags_str = if !flags.is_empty() {
                     format!(" [{}]", flags.join(", "))
@@ -114,15 +170,90 @@ impl TXError {
                     "     {} {}: {}{}",
                     "Account".dimmed(),
                     j.to_string().bold(),
-                    self.address_book.format_address(&account_key),
+                    format_account(account_index),
                     flags_str
                 );
             }
         }
 
-        // Print the address book for reference
+        self.balances.print(&self.address_book);
+    }
+}
+
+/// A successful transaction, along with the changes it made to its writable accounts.
+///
+/// Dereferences to the underlying [TransactionMetadata].
+#[derive(Debug)]
+pub struct TXSuccess {
+    /// The transaction that succeeded
+    pub transaction: VersionedTransaction,
+    /// Addresses loaded from address lookup tables by the transaction
+    pub loaded_addresses: LoadedAddresses,
+    /// Underlying transaction metadata
+    pub metadata: TransactionMetadata,
+    /// Changes to every writable account of the transaction
+    pub account_diffs: Vec<AccountDiff>,
+    /// SOL and token balances of every account of the transaction
+    pub balances: BalanceReport,
+    /// Address book at the time of the transaction
+    pub address_book: Arc<AddressBook>,
+}
+
+impl Deref for TXSuccess {
+    type Target = TransactionMetadata;
+
+    fn deref(&self) -> &Self::Target {
+        &self.metadata
+    }
+}
+
+impl TXSuccess {
+    /// Returns all account keys of the transaction, including those loaded from lookup tables.
+    pub fn account_keys(&self) -> AccountKeys<'_> {
+        AccountKeys::new(
+            self.transaction.message.static_account_keys(),
+            Some(&self.loaded_addresses),
+        )
+    }
+
+    /// Returns the diff of the given account, if it was writable in the transaction.
+    pub fn account_diff(&self, pubkey: &Pubkey) -> Option<&AccountDiff> {
+        self.account_diffs
+            .iter()
+            .find(|diff| diff.pubkey == *pubkey)
+    }
+
+    /// Returns the program invocations of the transaction, parsed from its logs.
+    pub fn invocation_tree(&self) -> InvocationTree {
+        InvocationTree::parse(&self.metadata.logs)
+    }
+
+    /// Print the SOL and token balances of every account, formatted using an [AddressBook].
+    pub fn print_balances(&self) {
+        self.balances.print(&self.address_book);
+    }
+
+    /// Print the changes made to every writable account, formatted using an [AddressBook].
+    ///
+    /// Writable accounts that did not change are omitted.
+    pub fn print_account_diffs(&self) {
+        let changed = self
+            .account_diffs
+            .iter()
+            .filter(|diff| diff.is_changed())
+            .collect::<Vec<_>>();
+        println!(
+            "\n{} {} ({} changed, {} unchanged)",
+            "🔀".cyan(),
+            "Account changes:".cyan().bold(),
+            changed.len(),
+            self.account_diffs.len() - changed.len()
+        );
+        for diff in changed {
+            diff.print(&self.address_book);
+        }
     }
 }
 
 /// A result type that represents the result of a transaction.
-pub type TXResult = Result<TransactionMetadata, Box<TXError>>;
+pub type TXResult = Result<TXSuccess, Box<TXError>>;
@@ -0,0 +1,198 @@
+//! # Upgradeable Programs
+//!
+//! Deployment and management of programs owned by the BPF upgradeable loader.
+//!
+//! [TestSVM::add_program_from_path] loads programs as immutable. The helpers in this module
+//! instead deploy programs owned by the upgradeable loader, so that upgrades and migrations
+//! can be tested:
+//!
+//! - **Deploy**: Create the program and its ProgramData account with [TestSVM::deploy_upgradeable_program]
+//! - **Upgrade**: Replace the program binary with [TestSVM::upgrade_program]
+//! - **Authority**: Transfer or revoke the upgrade authority with [TestSVM::set_program_upgrade_authority]
+//! - **Close**: Close the program and reclaim its rent with [TestSVM::close_program]
+//!
+//! Program binaries are written into buffer accounts directly rather than with hundreds of
+//! `Write` instructions. Upgrades, authority changes and closes are then regular loader
+//! transactions, and are recorded in the [TXHistory](crate::TXHistory).
+//!
+//! Like on a real cluster, a program cannot be upgraded or closed in the same slot it was
+//! deployed or last upgraded in. Advance the clock in between, e.g. with [TestSVM::advance_slots].
+
+use std::{fs, path::Path};
+
+use anyhow::*;
+use solana_loader_v3_interface::{
+    get_program_data_address, instruction as loader_instruction, state::UpgradeableLoaderState,
+};
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable,
+    clock::Clock,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use crate::{TXResult, TestSVM};
+
+impl TestSVM {
+    /// Deploys a program owned by the upgradeable loader and adds it to the address book.
+    ///
+    /// The program and its ProgramData account are written directly, as `solana-test-validator`
+    /// does for `--upgradeable-program`, since new deployments through the loader are disabled
+    /// when all features are enabled. This also allows programs to be deployed at their real
+    /// addresses without their keypairs. The ProgramData account is labeled
+    /// `{label}.program_data`, and as with `solana program deploy`, twice the size of the
+    /// binary is allocated for future upgrades.
+    pub fn deploy_upgradeable_program(
+        &mut self,
+        label: &str,
+        program_id: Pubkey,
+        path: impl AsRef<Path>,
+        upgrade_authority: &Keypair,
+    ) -> Result<()> {
+        let program = read_program(path.as_ref())?;
+        let (program_data, _) = self.address_book_mut().find_pda_with_bump(
+            &format!("{label}.program_data"),
+            &[program_id.as_ref()],
+            bpf_loader_upgradeable::ID,
+        )?;
+
+        let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+        let len = UpgradeableLoaderState::size_of_programdata(program.len() * 2);
+        let mut program_data_account = Account::new_data_with_space(
+            self.svm.minimum_balance_for_rent_exemption(len),
+            &UpgradeableLoaderState::ProgramData {
+                slot: self.svm.get_sysvar::<Clock>().slot,
+                upgrade_authority_address: Some(upgrade_authority.pubkey()),
+            },
+            len,
+            &bpf_loader_upgradeable::ID,
+        )?;
+        program_data_account.data[metadata_len..metadata_len + program.len()]
+            .copy_from_slice(&program);
+        self.set_account(&program_data, program_data_account)?;
+
+        let mut program_account = Account::new_data(
+            self.svm
+                .minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program()),
+            &UpgradeableLoaderState::Program {
+                programdata_address: program_data,
+            },
+            &bpf_loader_upgradeable::ID,
+        )?;
+        program_account.executable = true;
+        self.set_account(&program_id, program_account)?;
+
+        self.address_book_mut().add_program(program_id, label)
+    }
+
+    /// Upgrades a program deployed with [TestSVM::deploy_upgradeable_program] to a new binary.
+    ///
+    /// The new binary must fit in the space allocated for the program when it was deployed.
+    /// The transaction result is returned so that failed upgrades can be asserted on.
+    pub fn upgrade_program(
+        &mut self,
+        program_id: Pubkey,
+        path: impl AsRef<Path>,
+        authority: &Keypair,
+    ) -> Result<TXResult> {
+        let program = read_program(path.as_ref())?;
+        let label = self.address_book.get_label(&program_id);
+        let buffer = self.write_buffer(&label, &program, &authority.pubkey())?;
+
+        let upgrade_ix = loader_instruction::upgrade(
+            &program_id,
+            &buffer,
+            &authority.pubkey(),
+            &self.default_fee_payer(),
+        );
+        Ok(self.execute_ixs_with_signers(&[upgrade_ix], &[authority]))
+    }
+
+    /// Sets the upgrade authority of a program, or makes it immutable if `new_authority` is `None`.
+    pub fn set_program_upgrade_authority(
+        &mut self,
+        program_id: Pubkey,
+        authority: &Keypair,
+        new_authority: Option<&Pubkey>,
+    ) -> TXResult {
+        let set_authority_ix = loader_instruction::set_upgrade_authority(
+            &program_id,
+            &authority.pubkey(),
+            new_authority,
+        );
+        self.execute_ixs_with_signers(&[set_authority_ix], &[authority])
+    }
+
+    /// Closes a program, sending the lamports of its ProgramData account to `recipient`.
+    ///
+    /// LiteSVM never evicts programs from its cache, so the program account is also marked as
+    /// non-executable once the close succeeds. As on a real cluster, the closed program can
+    /// then no longer be invoked.
+    pub fn close_program(
+        &mut self,
+        program_id: Pubkey,
+        authority: &Keypair,
+        recipient: &Pubkey,
+    ) -> Result<TXResult> {
+        let close_ix = loader_instruction::close_any(
+            &get_program_data_address(&program_id),
+            recipient,
+            Some(&authority.pubkey()),
+            Some(&program_id),
+        );
+        let result = self.execute_ixs_with_signers(&[close_ix], &[authority]);
+        if result.is_ok() {
+            let program_account = self
+                .svm
+                .get_account(&program_id)
+                .with_context(|| format!("Program account not found for {program_id}"))?;
+            self.set_account(
+                &program_id,
+                Account {
+                    executable: false,
+                    ..program_account
+                },
+            )?;
+        }
+        Ok(result)
+    }
+
+    /// Returns the upgrade authority of a program, or `None` if it is immutable.
+    pub fn program_upgrade_authority(&self, program_id: &Pubkey) -> Result<Option<Pubkey>> {
+        let program_data = get_program_data_address(program_id);
+        let account = self
+            .svm
+            .get_account(&program_data)
+            .with_context(|| format!("ProgramData account not found for {program_id}"))?;
+        match account.deserialize_data()? {
+            UpgradeableLoaderState::ProgramData {
+                upgrade_authority_address,
+                ..
+            } => Ok(upgrade_authority_address),
+            _ => bail!("Invalid ProgramData account for {program_id}"),
+        }
+    }
+
+    /// Writes a program binary into a new buffer account owned by the upgradeable loader.
+    fn write_buffer(&mut self, label: &str, program: &[u8], authority: &Pubkey) -> Result<Pubkey> {
+        let buffer = self.new_keypair(&format!("{label}.buffer")).pubkey();
+        let metadata_len = UpgradeableLoaderState::size_of_buffer_metadata();
+        let len = UpgradeableLoaderState::size_of_buffer(program.len());
+        let mut account = Account::new_data_with_space(
+            self.svm.minimum_balance_for_rent_exemption(len),
+            &UpgradeableLoaderState::Buffer {
+                authority_address: Some(*authority),
+            },
+            len,
+            &bpf_loader_upgradeable::ID,
+        )?;
+        account.data[metadata_len..].copy_from_slice(program);
+        self.set_account(&buffer, account)?;
+        Ok(buffer)
+    }
+}
+
+fn read_program(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Failed to read program {}", path.display()))
+}
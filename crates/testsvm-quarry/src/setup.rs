@@ -27,6 +27,20 @@ use crate::quarry_mine;
 /// solana program dump QMNeHCGYnLVDn1icRAfQZpjPLBNkfGbSKRB83G5d8KB $ROOT_DIR/fixtures/programs/quarry_mine.so
 /// solana program dump QMWoBmAyJLAsA1Lh9ugMTw2gciTihncciphzdNzdZYV $ROOT_DIR/fixtures/programs/quarry_mint_wrapper.so
 /// ```
+///
+/// If `fixtures/manifest.toml` lists these binaries, they are verified against it as well.
+/// See [TestSVM::add_program_fixture].
+///
+/// This function can also be used as a [TestSVMPlugin]:
+///
+/// ```rust,no_run
+/// # use testsvm::prelude::*;
+/// # use testsvm_quarry::setup_quarry_programs;
+/// # fn main() -> anyhow::Result<()> {
+/// let env = TestSVM::builder().plugin(setup_quarry_programs).build()?;
+/// # Ok(())
+/// # }
+/// ```
 pub fn setup_quarry_programs(env: &mut TestSVM) -> Result<()> {
     env.add_program_fixture("quarry_mine", quarry_mine::ID)?;
     env.add_program_fixture("quarry_merge_mine", crate::quarry_merge_mine::ID)?;
@@ -81,20 +81,19 @@ impl TestQuarry {
         Ok((miner, miner_vault))
     }
 
-    /// Stake tokens into the miner
-    pub fn stake_tokens(
+    /// Build an instruction staking tokens into the miner
+    pub fn stake_tokens_ix(
         &self,
-        env: &mut TestSVM,
         miner: &AccountRef<quarry_mine::accounts::Miner>,
         miner_vault: &AccountRef<anchor_spl::token::TokenAccount>,
         user_token_account: &AccountRef<anchor_spl::token::TokenAccount>,
         amount: u64,
-        user: &Keypair,
-    ) -> Result<()> {
-        let stake_ix = anchor_instruction(
+        user: &Pubkey,
+    ) -> Instruction {
+        anchor_instruction(
             quarry_mine::ID,
             quarry_mine::client::accounts::StakeTokens {
-                authority: user.pubkey(),
+                authority: *user,
                 miner: miner.key,
                 quarry: self.quarry.key,
                 rewarder: self.rewarder,
@@ -103,11 +102,27 @@ impl TestQuarry {
                 token_program: anchor_spl::token::ID,
             },
             quarry_mine::client::args::StakeTokens { amount },
-        );
-
-        env.execute_ixs_with_signers(&[stake_ix], &[user])?;
+        )
+    }
 
-        Ok(())
+    /// Stake tokens into the miner, returning the successful transaction
+    pub fn stake_tokens(
+        &self,
+        env: &mut TestSVM,
+        miner: &AccountRef<quarry_mine::accounts::Miner>,
+        miner_vault: &AccountRef<anchor_spl::token::TokenAccount>,
+        user_token_account: &AccountRef<anchor_spl::token::TokenAccount>,
+        amount: u64,
+        user: &Keypair,
+    ) -> Result<TXSuccess> {
+        let stake_ix = self.stake_tokens_ix(
+            miner,
+            miner_vault,
+            user_token_account,
+            amount,
+            &user.pubkey(),
+        );
+        Ok(env.execute_ixs_with_signers(&[stake_ix], &[user])?)
     }
 
     /// Update quarry rewards to reflect time passage
@@ -126,20 +141,19 @@ impl TestQuarry {
         Ok(())
     }
 
-    /// Withdraw tokens from the miner
-    pub fn withdraw_tokens(
+    /// Build an instruction withdrawing tokens from the miner
+    pub fn withdraw_tokens_ix(
         &self,
-        env: &mut TestSVM,
         miner: &AccountRef<quarry_mine::accounts::Miner>,
         miner_vault: &AccountRef<anchor_spl::token::TokenAccount>,
         user_token_account: &AccountRef<anchor_spl::token::TokenAccount>,
         amount: u64,
-        user: &Keypair,
-    ) -> Result<()> {
-        let withdraw_ix = anchor_instruction(
+        user: &Pubkey,
+    ) -> Instruction {
+        anchor_instruction(
             quarry_mine::ID,
             quarry_mine::client::accounts::WithdrawTokens {
-                authority: user.pubkey(),
+                authority: *user,
                 miner: miner.key,
                 quarry: self.quarry.key,
                 rewarder: self.rewarder,
@@ -148,23 +162,37 @@ impl TestQuarry {
                 token_program: anchor_spl::token::ID,
             },
             quarry_mine::client::args::WithdrawTokens { amount },
-        );
-
-        env.execute_ixs_with_signers(&[withdraw_ix], &[user])?;
-
-        Ok(())
+        )
     }
 
-    /// Claim rewards for a miner
-    pub fn claim_rewards(
+    /// Withdraw tokens from the miner, returning the successful transaction
+    pub fn withdraw_tokens(
         &self,
         env: &mut TestSVM,
+        miner: &AccountRef<quarry_mine::accounts::Miner>,
+        miner_vault: &AccountRef<anchor_spl::token::TokenAccount>,
+        user_token_account: &AccountRef<anchor_spl::token::TokenAccount>,
+        amount: u64,
+        user: &Keypair,
+    ) -> Result<TXSuccess> {
+        let withdraw_ix = self.withdraw_tokens_ix(
+            miner,
+            miner_vault,
+            user_token_account,
+            amount,
+            &user.pubkey(),
+        );
+        Ok(env.execute_ixs_with_signers(&[withdraw_ix], &[user])?)
+    }
+
+    /// Build an instruction claiming the rewards of a miner
+    pub fn claim_rewards_ix(
+        &self,
         rewarder: &TestRewarder,
         miner: &AccountRef<quarry_mine::accounts::Miner>,
-        _miner_vault: &AccountRef<anchor_spl::token::TokenAccount>,
         user_rewards_account: &AccountRef<anchor_spl::token::TokenAccount>,
-        user: &Keypair,
-    ) -> Result<()> {
+        user: &Pubkey,
+    ) -> Instruction {
         // Find the minter PDA - it should already exist from perform_new_minter
         let (minter, _) = Pubkey::find_program_address(
             &[
@@ -175,7 +203,7 @@ impl TestQuarry {
             &quarry_mint_wrapper::ID,
         );
 
-        let claim_ix = anchor_instruction(
+        anchor_instruction(
             quarry_mine::ID,
             quarry_mine::client::accounts::ClaimRewardsV2 {
                 mint_wrapper: rewarder.mint_wrapper.mint_wrapper.key,
@@ -185,7 +213,7 @@ impl TestQuarry {
                 rewards_token_account: user_rewards_account.key,
                 claim_fee_token_account: rewarder.claim_fee_token_account.key,
                 claim: quarry_mine::client::accounts::Claim {
-                    authority: user.pubkey(),
+                    authority: *user,
                     miner: miner.key,
                     quarry: self.quarry.key,
                     token_program: anchor_spl::token::ID,
@@ -193,11 +221,21 @@ impl TestQuarry {
                 },
             },
             quarry_mine::client::args::ClaimRewardsV2 {},
-        );
-
-        env.execute_ixs_with_signers(&[claim_ix], &[user])?;
+        )
+    }
 
-        Ok(())
+    /// Claim rewards for a miner, returning the successful transaction
+    pub fn claim_rewards(
+        &self,
+        env: &mut TestSVM,
+        rewarder: &TestRewarder,
+        miner: &AccountRef<quarry_mine::accounts::Miner>,
+        _miner_vault: &AccountRef<anchor_spl::token::TokenAccount>,
+        user_rewards_account: &AccountRef<anchor_spl::token::TokenAccount>,
+        user: &Keypair,
+    ) -> Result<TXSuccess> {
+        let claim_ix = self.claim_rewards_ix(rewarder, miner, user_rewards_account, &user.pubkey());
+        Ok(env.execute_ixs_with_signers(&[claim_ix], &[user])?)
     }
 }
 
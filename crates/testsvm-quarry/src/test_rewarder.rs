@@ -243,7 +243,7 @@ impl TestRewarder {
 
         // Add the minter to address book after creation
         if result.is_ok() {
-            env.address_book.add_pda(
+            env.address_book_mut().add_pda(
                 minter,
                 format!("rewarder[{}].minter[{}]", self.label, label),
                 vec![
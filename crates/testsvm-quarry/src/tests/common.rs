@@ -2,9 +2,78 @@ use anyhow::Result;
 use testsvm::prelude::*;
 
 use crate::setup_quarry_programs;
+use crate::test_quarry::TestQuarry;
+use crate::test_rewarder::TestRewarder;
 
 pub fn init_test_environment() -> Result<TestSVM> {
-    let mut env = TestSVM::init()?;
-    setup_quarry_programs(&mut env)?;
-    Ok(env)
+    TestSVM::builder().plugin(setup_quarry_programs).build()
+}
+
+/// Create an associated token account for `owner` and mint `amount` tokens into it
+pub fn create_and_fund_token_account(
+    env: &mut TestSVM,
+    label: &str,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    mint_authority: &Keypair,
+) -> Result<AccountRef<anchor_spl::token::TokenAccount>> {
+    // Create ATA
+    let (create_ata_ix, token_account) = env.create_ata_ix(label, owner, mint)?;
+    env.execute_ixs(&[create_ata_ix])?;
+
+    // Mint tokens
+    let mint_to_ix = anchor_spl::token::spl_token::instruction::mint_to(
+        &anchor_spl::token::ID,
+        mint,
+        &token_account.key,
+        &mint_authority.pubkey(),
+        &[],
+        amount,
+    )?;
+
+    env.execute_ixs_with_signers(&[mint_to_ix], &[mint_authority])?;
+
+    Ok(token_account)
+}
+
+/// A quarry paying 1,000,000 reward tokens per year, and a user holding 1,000 staked tokens
+/// who has not created a miner yet.
+pub struct QuarryTestSetup {
+    pub env: TestSVM,
+    pub user: Keypair,
+    pub rewarder: TestRewarder,
+    pub staked_token_mint: AccountRef<anchor_spl::token::Mint>,
+    pub quarry: TestQuarry,
+    pub user_staked_tokens: AccountRef<anchor_spl::token::TokenAccount>,
+}
+
+pub fn setup_quarry() -> Result<QuarryTestSetup> {
+    let mut env = init_test_environment()?;
+    let authority = env.new_wallet("authority")?;
+    let user = env.new_wallet("user")?;
+
+    let rewarder = TestRewarder::new_rewarder(&mut env, "main", &authority)?;
+    let staked_token_mint = env.create_mint("staked_token", 6, &authority.pubkey())?;
+    let quarry = rewarder.create_quarry(&mut env, "main", &staked_token_mint.key, &authority)?;
+    rewarder.set_annual_rewards_rate(&mut env, 1_000_000 * 10u64.pow(6), &authority)?;
+    let _ = rewarder.new_minter(&mut env, "main", &authority)?;
+
+    let user_staked_tokens = create_and_fund_token_account(
+        &mut env,
+        "user_staked_tokens",
+        &user.pubkey(),
+        &staked_token_mint.key,
+        1000 * 10u64.pow(6),
+        &authority,
+    )?;
+
+    Ok(QuarryTestSetup {
+        env,
+        user,
+        rewarder,
+        staked_token_mint,
+        quarry,
+        user_staked_tokens,
+    })
 }
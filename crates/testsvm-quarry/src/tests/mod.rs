@@ -1,6 +1,11 @@
 pub mod common;
+pub mod test_account_assertions;
+pub mod test_account_fixtures;
+pub mod test_cheats;
 pub mod test_claim_rewards;
 pub mod test_deposit_withdraw;
+pub mod test_events;
 pub mod test_mint_wrapper_actions;
 pub mod test_quarry_setup;
 pub mod test_rewarder_management;
+pub mod test_upgradeable_programs;
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use testsvm::prelude::*;
+
+use super::common::{QuarryTestSetup, setup_quarry};
+
+#[test]
+fn test_assert_miner_state() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+    quarry.stake_tokens(
+        &mut env,
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user,
+    )?;
+
+    env.assert_account(&miner)?
+        .field(|m| m.balance, 100 * 10u64.pow(6))?
+        .field(|m| m.authority, user.pubkey())?
+        .field(|m| m.quarry, quarry.quarry.key)?;
+
+    // Mismatched fields are reported as errors
+    assert!(
+        env.assert_account(&miner)?
+            .field(|m| m.balance, 99 * 10u64.pow(6))
+            .is_err()
+    );
+
+    // Whole accounts are compared field by field
+    let expected = miner.load(&env)?;
+    assert_account_eq(&env, &miner, &expected)?;
+
+    let mut stale = miner.load(&env)?;
+    stale.balance = 0;
+    assert!(assert_account_eq(&env, &miner, &stale).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use testsvm::prelude::*;
+
+use crate::quarry_mine;
+
+use super::common::init_test_environment;
+
+#[test]
+fn test_load_rewarder_fixture() -> Result<()> {
+    let mut env = init_test_environment()?;
+
+    let loaded = env.load_account_fixtures_dir()?;
+    assert_eq!(loaded.len(), 2);
+
+    // Accounts are labeled by file name and assigned a role based on their owner
+    let rewarder_key = env
+        .address_book
+        .get_by_role(&AddressRole::Custom("quarry_mine".to_string()))
+        .expect("rewarder should be registered under its owning program");
+    assert_eq!(env.address_book.get_label(&rewarder_key), "quarry_rewarder");
+
+    let mint_key = env
+        .address_book
+        .get_all_by_role_type("mint")
+        .into_iter()
+        .find(|key| loaded.contains(key))
+        .expect("rewards mint should be registered as a mint");
+    assert_eq!(env.address_book.get_label(&mint_key), "quarry_rewards_mint");
+
+    // The dumped state can be read back through typed account refs
+    let rewarder: AccountRef<quarry_mine::accounts::Rewarder> = AccountRef::new(rewarder_key);
+    let rewarder_data = rewarder.load(&env)?;
+    assert_eq!(rewarder_data.rewards_token_mint, mint_key);
+    assert_eq!(rewarder_data.annual_rewards_rate, 1_000_000 * 10u64.pow(6));
+
+    let mint: AccountRef<anchor_spl::token::Mint> = AccountRef::new(mint_key);
+    assert_eq!(mint.load(&env)?.decimals, 6);
+
+    Ok(())
+}
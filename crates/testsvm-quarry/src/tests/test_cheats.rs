@@ -0,0 +1,120 @@
+use anyhow::Result;
+use solana_sdk::clock::Clock;
+use testsvm::prelude::*;
+
+use crate::quarry_mine;
+use crate::test_rewarder::TestRewarder;
+
+use super::common::{QuarryTestSetup, init_test_environment, setup_quarry};
+
+#[test]
+fn test_famine_in_the_past() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+    quarry.stake_tokens(
+        &mut env,
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user,
+    )?;
+
+    // End the reward period immediately, without going through the rewarder authority
+    let now = env.svm.get_sysvar::<Clock>().unix_timestamp;
+    quarry
+        .quarry
+        .modify(&mut env, |quarry| quarry.famine_ts = now)?;
+    assert_eq!(quarry.fetch_quarry(&env)?.famine_ts, now);
+
+    // No rewards accrue after the famine
+    env.advance_time(365 * 24 * 60 * 60);
+    quarry.update_quarry_rewards(&mut env)?;
+    let quarry_state = quarry.fetch_quarry(&env)?;
+    assert_eq!(quarry_state.rewards_per_token_stored, 0);
+    assert_eq!(quarry_state.last_update_ts, now);
+
+    Ok(())
+}
+
+#[test]
+fn test_claim_stored_rewards() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        rewarder,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+    quarry.stake_tokens(
+        &mut env,
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user,
+    )?;
+
+    // Give the miner rewards without waiting for them to accrue
+    let rewards_earned = 5_000 * 10u64.pow(6);
+    miner.modify(&mut env, |miner| miner.rewards_earned = rewards_earned)?;
+
+    let (create_ata_ix, user_rewards) = env.create_ata_ix(
+        "user_rewards",
+        &user.pubkey(),
+        &rewarder.mint_wrapper.reward_token_mint.key,
+    )?;
+    env.execute_ixs(&[create_ata_ix])?;
+    let claim = quarry.claim_rewards(
+        &mut env,
+        &rewarder,
+        &miner,
+        &miner_vault,
+        &user_rewards,
+        &user,
+    )?;
+
+    // The stored rewards are paid out, split between the user and the claim fee
+    let claimed = claim.balances.token(&user_rewards.key).unwrap().change();
+    let fees = claim
+        .balances
+        .token(&rewarder.claim_fee_token_account.key)
+        .unwrap()
+        .change();
+    assert_eq!(claimed + fees, rewards_earned as i128);
+    let miner_state: quarry_mine::accounts::Miner = miner.load(&env)?;
+    assert_eq!(miner_state.rewards_earned, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_impersonate_rewarder_authority() -> Result<()> {
+    let mut env = init_test_environment()?;
+    let authority = env.new_wallet("authority")?;
+    let rewarder = TestRewarder::new_rewarder(&mut env, "main", &authority)?;
+
+    // Act as the authority using only its address, as with a rewarder loaded from mainnet
+    let set_rewards_ix = anchor_instruction(
+        quarry_mine::ID,
+        quarry_mine::client::accounts::SetAnnualRewards {
+            auth: quarry_mine::client::accounts::TransferAuthority {
+                authority: authority.pubkey(),
+                rewarder: rewarder.rewarder.key,
+            },
+        },
+        quarry_mine::client::args::SetAnnualRewards { new_rate: 42 },
+    );
+    env.execute_ixs_as(&[set_rewards_ix], &[authority.pubkey()])??;
+    assert_eq!(rewarder.fetch_rewarder(&env)?.annual_rewards_rate, 42);
+
+    Ok(())
+}
@@ -2,9 +2,62 @@ use anyhow::Result;
 use testsvm::prelude::*;
 
 use crate::quarry_mine;
+use crate::test_quarry::TestQuarry;
 use crate::test_rewarder::TestRewarder;
 
-use super::common::init_test_environment;
+use super::common::{
+    QuarryTestSetup, create_and_fund_token_account, init_test_environment, setup_quarry,
+};
+
+/// A user who has staked 100 tokens for a year in a quarry paying 1,000,000 reward tokens
+/// per year, and has an empty reward token account.
+struct ClaimTestSetup {
+    env: TestSVM,
+    user: Keypair,
+    rewarder: TestRewarder,
+    quarry: TestQuarry,
+    miner: AccountRef<quarry_mine::accounts::Miner>,
+    miner_vault: AccountRef<anchor_spl::token::TokenAccount>,
+    user_rewards: AccountRef<anchor_spl::token::TokenAccount>,
+}
+
+fn setup_claim() -> Result<ClaimTestSetup> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        rewarder,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+    quarry.stake_tokens(
+        &mut env,
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user,
+    )?;
+
+    let (create_ata_ix, user_rewards) = env.create_ata_ix(
+        "user_rewards",
+        &user.pubkey(),
+        &rewarder.mint_wrapper.reward_token_mint.key,
+    )?;
+    env.execute_ixs(&[create_ata_ix])?;
+    env.advance_time(365 * 24 * 60 * 60);
+
+    Ok(ClaimTestSetup {
+        env,
+        user,
+        rewarder,
+        quarry,
+        miner,
+        miner_vault,
+        user_rewards,
+    })
+}
 
 #[test]
 fn test_claim_rewards() -> Result<()> {
@@ -122,6 +175,130 @@ fn test_claim_rewards() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_claim_rewards_balance_report() -> Result<()> {
+    let ClaimTestSetup {
+        mut env,
+        user,
+        rewarder,
+        quarry,
+        miner,
+        miner_vault,
+        user_rewards,
+    } = setup_claim()?;
+
+    let claim = quarry.claim_rewards(
+        &mut env,
+        &rewarder,
+        &miner,
+        &miner_vault,
+        &user_rewards,
+        &user,
+    )?;
+
+    // The reward token account is found by its owner and mint
+    let reward_balance = claim
+        .balances
+        .token_by_owner(&user.pubkey(), &rewarder.mint_wrapper.reward_token_mint.key)
+        .unwrap();
+    assert_eq!(reward_balance.pubkey, user_rewards.key);
+    assert_eq!(reward_balance.pre, Some(0));
+    assert_eq!(reward_balance.post, Some(user_rewards.load(&env)?.amount));
+    assert_eq!(reward_balance.decimals, Some(6));
+    assert!(reward_balance.change() >= 999_000 * 10i128.pow(6));
+
+    // The claim fee is paid to the rewarder's claim fee account
+    let claim_fee = claim
+        .balances
+        .token(&rewarder.claim_fee_token_account.key)
+        .unwrap();
+    assert!(claim_fee.change() > 0, "Claim fee should be collected");
+
+    Ok(())
+}
+
+#[test]
+fn test_claim_rewards_invocation_tree() -> Result<()> {
+    let ClaimTestSetup {
+        mut env,
+        user,
+        rewarder,
+        quarry,
+        miner,
+        miner_vault,
+        user_rewards,
+    } = setup_claim()?;
+
+    let claim = quarry.claim_rewards(
+        &mut env,
+        &rewarder,
+        &miner,
+        &miner_vault,
+        &user_rewards,
+        &user,
+    )?;
+
+    // The rewards are minted through the mint wrapper
+    let invocations = claim.invocation_tree();
+    assert_eq!(invocations.roots.len(), 1);
+    assert_eq!(invocations.roots[0].program_id, quarry_mine::ID);
+    let mint_wrapper_call = invocations
+        .invocations_of(&crate::quarry_mint_wrapper::ID)
+        .next()
+        .unwrap();
+    assert_eq!(mint_wrapper_call.depth, 2);
+    assert_eq!(
+        mint_wrapper_call.children[0].program_id,
+        anchor_spl::token::ID
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_claim_rewards_compute_unit_profile() -> Result<()> {
+    let ClaimTestSetup {
+        mut env,
+        user,
+        rewarder,
+        quarry,
+        miner,
+        miner_vault,
+        user_rewards,
+    } = setup_claim()?;
+
+    env.enable_compute_unit_profiling();
+    let claim = quarry.claim_rewards(
+        &mut env,
+        &rewarder,
+        &miner,
+        &miner_vault,
+        &user_rewards,
+        &user,
+    )?;
+
+    let profile = env.compute_unit_profile.as_ref().unwrap();
+    assert_eq!(profile.transactions(), 1);
+    let claims = profile.instructions()["quarry_mine::ClaimRewardsV2"];
+    assert_eq!(claims.count, 1);
+    assert_eq!(claims.total, claim.compute_units_consumed);
+    assert!(profile.cpi_programs().contains_key("quarry_mint_wrapper"));
+
+    // The self compute units of every call stack add up to the whole claim
+    assert_eq!(
+        profile.folded_stacks().values().sum::<u64>(),
+        claim.compute_units_consumed
+    );
+    assert!(
+        profile
+            .folded_stacks()
+            .keys()
+            .any(|stack| stack.starts_with("quarry_mine::ClaimRewardsV2;quarry_mint_wrapper"))
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_claim_rewards_wrong_authority() -> Result<()> {
     let mut env = init_test_environment()?;
@@ -366,29 +543,137 @@ fn test_claim_rewards_multiple_users() -> Result<()> {
     Ok(())
 }
 
-fn create_and_fund_token_account(
-    env: &mut TestSVM,
-    label: &str,
-    owner: &Pubkey,
-    mint: &Pubkey,
-    amount: u64,
-    mint_authority: &Keypair,
-) -> Result<AccountRef<anchor_spl::token::TokenAccount>> {
-    // Create ATA
-    let (create_ata_ix, token_account) = env.create_ata_ix(label, owner, mint)?;
-    env.execute_ixs(&[create_ata_ix])?;
+#[test]
+fn test_claim_rewards_branches_from_snapshot() -> Result<()> {
+    let ClaimTestSetup {
+        mut env,
+        user,
+        rewarder,
+        quarry,
+        miner,
+        miner_vault,
+        user_rewards,
+    } = setup_claim()?;
+
+    let snapshot = env.snapshot();
+
+    // Branch 1: claim after the first year
+    quarry.claim_rewards(
+        &mut env,
+        &rewarder,
+        &miner,
+        &miner_vault,
+        &user_rewards,
+        &user,
+    )?;
+    let one_year_rewards = user_rewards.load(&env)?.amount;
 
-    // Mint tokens
-    let mint_to_ix = anchor_spl::token::spl_token::instruction::mint_to(
-        &anchor_spl::token::ID,
-        mint,
-        &token_account.key,
-        &mint_authority.pubkey(),
-        &[],
-        amount,
+    // Branch 2: claim after another year from the same starting point
+    env.restore(&snapshot);
+    assert_eq!(user_rewards.load(&env)?.amount, 0);
+    env.advance_time(365 * 24 * 60 * 60);
+    quarry.claim_rewards(
+        &mut env,
+        &rewarder,
+        &miner,
+        &miner_vault,
+        &user_rewards,
+        &user,
     )?;
+    let two_year_rewards = user_rewards.load(&env)?.amount;
 
-    env.execute_ixs_with_signers(&[mint_to_ix], &[mint_authority])?;
+    assert!(one_year_rewards > 0);
+    assert!(
+        two_year_rewards.abs_diff(one_year_rewards * 2) <= 10u64.pow(6),
+        "Two years should yield twice the rewards. One year: {one_year_rewards}, two years: {two_year_rewards}"
+    );
 
-    Ok(token_account)
+    Ok(())
+}
+
+#[test]
+fn test_simulate_claim_rewards() -> Result<()> {
+    let ClaimTestSetup {
+        mut env,
+        user,
+        rewarder,
+        quarry,
+        miner,
+        user_rewards,
+        ..
+    } = setup_claim()?;
+
+    // Simulate the claim to find out how many rewards it would mint
+    let claim_ix = quarry.claim_rewards_ix(&rewarder, &miner, &user_rewards, &user.pubkey());
+    let transactions = env.history.len();
+    let rewards_per_token_paid = miner.load(&env)?.rewards_per_token_paid;
+    let simulation = env
+        .simulate_ixs_with_signers(std::slice::from_ref(&claim_ix), &[&user])
+        .succeeds()?;
+    let simulated_rewards = simulation
+        .success
+        .balances
+        .token(&user_rewards.key)
+        .unwrap()
+        .change();
+    assert!(simulated_rewards >= 999_000 * 10i128.pow(6));
+    assert!(
+        simulation
+            .success
+            .invocation_tree()
+            .invocations_of(&crate::quarry_mint_wrapper::ID)
+            .next()
+            .is_some()
+    );
+
+    // Nothing was committed
+    assert_eq!(user_rewards.load(&env)?.amount, 0);
+    assert_eq!(
+        miner.load(&env)?.rewards_per_token_paid,
+        rewards_per_token_paid
+    );
+    assert_eq!(env.history.len(), transactions);
+
+    // Executing the claim mints exactly the simulated rewards
+    env.execute_ixs_with_signers(&[claim_ix], &[&user])
+        .succeeds()?
+        .token_balance_changed(&user_rewards, simulated_rewards)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_simulate_claim_rewards_wrong_authority() -> Result<()> {
+    let ClaimTestSetup {
+        mut env,
+        rewarder,
+        quarry,
+        miner,
+        ..
+    } = setup_claim()?;
+    let wrong_user = env.new_wallet("wrong_user")?;
+
+    let (create_ata_ix, wrong_user_rewards) = env.create_ata_ix(
+        "wrong_user_rewards",
+        &wrong_user.pubkey(),
+        &rewarder.mint_wrapper.reward_token_mint.key,
+    )?;
+    env.execute_ixs(&[create_ata_ix])?;
+
+    // A failed simulation is reported like a failed transaction, but is not recorded
+    let transactions = env.history.len();
+    let wrong_user_lamports = env.svm.get_balance(&wrong_user.pubkey());
+    let claim_ix =
+        quarry.claim_rewards_ix(&rewarder, &miner, &wrong_user_rewards, &wrong_user.pubkey());
+    let error = env
+        .simulate_ixs_with_signers(&[claim_ix], &[&wrong_user])
+        .fails()?;
+    error.with_anchor_error("KeyMismatch")?;
+    assert_eq!(env.history.len(), transactions);
+    assert_eq!(
+        env.svm.get_balance(&wrong_user.pubkey()),
+        wrong_user_lamports
+    );
+
+    Ok(())
 }
@@ -1,10 +1,17 @@
+use anchor_lang::prelude::error_code;
 use anyhow::Result;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::InstructionError,
+    transaction::TransactionError,
+};
 use testsvm::prelude::*;
 
 use crate::quarry_mine;
 use crate::test_rewarder::TestRewarder;
 
-use super::common::init_test_environment;
+use super::common::{
+    QuarryTestSetup, create_and_fund_token_account, init_test_environment, setup_quarry,
+};
 
 #[test]
 fn test_deposit_and_withdraw() -> Result<()> {
@@ -179,6 +186,57 @@ fn test_withdraw_more_than_staked() -> Result<()> {
     Ok(())
 }
 
+/// Mirrors the start of the `ErrorCode` enum of `quarry_mine`.
+#[error_code]
+enum QuarryMineErrorCode {
+    #[msg("You are not authorized to perform this action.")]
+    Unauthorized,
+    #[msg("Insufficient staked balance for withdraw request.")]
+    InsufficientBalance,
+}
+
+#[test]
+fn test_withdraw_more_than_staked_error_code() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+    quarry.stake_tokens(
+        &mut env,
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user,
+    )?;
+
+    let withdraw_ix = quarry.withdraw_tokens_ix(
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        200 * 10u64.pow(6),
+        &user.pubkey(),
+    );
+    let assertions = env
+        .execute_ixs_with_signers(&[withdraw_ix], &[&user])
+        .fails()?;
+
+    // Program-defined errors are numbered from the error code offset
+    assertions.with_anchor_error_code(QuarryMineErrorCode::InsufficientBalance)?;
+    assertions.with_custom_error(anchor_lang::error::ERROR_CODE_OFFSET + 1)?;
+    assert!(
+        assertions
+            .with_anchor_error_code(QuarryMineErrorCode::Unauthorized)
+            .is_err()
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_withdraw_wrong_authority() -> Result<()> {
     let mut env = init_test_environment()?;
@@ -243,6 +301,54 @@ fn test_withdraw_wrong_authority() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_stake_into_wrong_quarry_account() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        rewarder,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+
+    // Pass a token account where the quarry is expected, failing Anchor's owner check
+    let stake_ix = anchor_instruction(
+        quarry_mine::ID,
+        quarry_mine::client::accounts::StakeTokens {
+            authority: user.pubkey(),
+            miner: miner.key,
+            quarry: user_staked_tokens.key,
+            rewarder: rewarder.rewarder.key,
+            token_account: user_staked_tokens.key,
+            miner_vault: miner_vault.key,
+            token_program: anchor_spl::token::ID,
+        },
+        quarry_mine::client::args::StakeTokens {
+            amount: 100 * 10u64.pow(6),
+        },
+    );
+    let assertions = env
+        .execute_ixs_with_signers(&[stake_ix], &[&user])
+        .fails()?;
+    assertions.with_anchor_error_code(anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram)?;
+
+    // The owner constraint compares the actual owner with the expected one
+    assertions
+        .at_instruction(0)?
+        .caused_by_account("quarry")?
+        .with_constraint_values(anchor_spl::token::ID, quarry_mine::ID)?;
+    assert!(assertions.caused_by_account("miner").is_err());
+    assert!(
+        assertions
+            .with_constraint_values(quarry_mine::ID, anchor_spl::token::ID)
+            .is_err()
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_multiple_deposits_and_withdrawals() -> Result<()> {
     let mut env = init_test_environment()?;
@@ -347,29 +453,166 @@ fn test_multiple_deposits_and_withdrawals() -> Result<()> {
     Ok(())
 }
 
-fn create_and_fund_token_account(
-    env: &mut TestSVM,
-    label: &str,
-    owner: &Pubkey,
-    mint: &Pubkey,
-    amount: u64,
-    mint_authority: &Keypair,
-) -> Result<AccountRef<anchor_spl::token::TokenAccount>> {
-    // Create ATA
-    let (create_ata_ix, token_account) = env.create_ata_ix(label, owner, mint)?;
-    env.execute_ixs(&[create_ata_ix])?;
-
-    // Mint tokens
-    let mint_to_ix = anchor_spl::token::spl_token::instruction::mint_to(
-        &anchor_spl::token::ID,
-        mint,
-        &token_account.key,
-        &mint_authority.pubkey(),
-        &[],
-        amount,
+#[test]
+fn test_withdraw_balance_changes() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+    quarry.stake_tokens(
+        &mut env,
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        300 * 10u64.pow(6),
+        &user,
+    )?;
+
+    let withdraw_ix = quarry.withdraw_tokens_ix(
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user.pubkey(),
+    );
+    let assertions = env
+        .execute_ixs_with_signers(&[withdraw_ix], &[&user])
+        .succeeds()?;
+
+    assertions
+        .token_balance_changed(&user_staked_tokens, 100 * 10i128.pow(6))?
+        .token_balance_changed(&miner_vault, -100 * 10i128.pow(6))?
+        .lamports_changed(&user.pubkey(), 0)?;
+
+    // Only the user's tokens and the vault moved
+    assertions
+        .expect_balance_changes()
+        .tokens(&user_staked_tokens, 100 * 10i128.pow(6))
+        .tokens(&miner_vault, -100 * 10i128.pow(6))
+        .verify()?;
+
+    // A missing expected change is reported
+    assert!(
+        assertions
+            .expect_balance_changes()
+            .tokens(&user_staked_tokens, 100 * 10i128.pow(6))
+            .verify()
+            .is_err()
+    );
+
+    // Withdrawals stay well within the default compute budget
+    assertions.with_max_compute_units(200_000)?;
+    assert!(assertions.with_max_compute_units(1).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_stake_into_uninitialized_miner() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        staked_token_mint,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+
+    // Derive the miner without creating it
+    let miner: AccountRef<quarry_mine::accounts::Miner> = env.get_pda(
+        "miner_user",
+        &[b"Miner", quarry.quarry.key.as_ref(), user.pubkey().as_ref()],
+        quarry_mine::ID,
     )?;
+    let (create_vault_ix, miner_vault) =
+        env.create_ata_ix("miner_vault_user", &miner.key, &staked_token_mint.key)?;
+    env.execute_ixs(&[create_vault_ix])?;
+
+    let stake_ix = quarry.stake_tokens_ix(
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user.pubkey(),
+    );
+    let assertions = env
+        .execute_ixs_with_signers(&[stake_ix], &[&user])
+        .fails()?;
+    assertions.with_anchor_error_code(anchor_lang::error::ErrorCode::AccountNotInitialized)?;
+    assertions.at_instruction(0)?.caused_by_account("miner")?;
+    assert!(assertions.at_instruction(1).is_err());
+    assert!(assertions.caused_by_account("quarry").is_err());
+
+    // Both the error number and the logged name must match
+    assert!(
+        assertions
+            .with_anchor_error_code(anchor_lang::error::ErrorCode::ConstraintSeeds)
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_stake_exceeds_compute_budget() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+
+    // The stake runs out of compute units partway through quarry_mine, which the runtime
+    // reports as ProgramFailedToComplete
+    let limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(2_000);
+    let stake_ix = quarry.stake_tokens_ix(
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user.pubkey(),
+    );
+    env.execute_ixs_with_signers(&[limit_ix, stake_ix], &[&user])
+        .fails()?
+        .with_compute_budget_exceeded()?
+        .with_transaction_error(|err| {
+            matches!(
+                err,
+                TransactionError::InstructionError(1, InstructionError::ProgramFailedToComplete)
+            )
+        })?;
+
+    Ok(())
+}
+
+#[test]
+fn test_stake_compute_units() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
 
-    env.execute_ixs_with_signers(&[mint_to_ix], &[mint_authority])?;
+    // Staking must not regress against the compute units recorded in compute_units.json
+    let stake_ix = quarry.stake_tokens_ix(
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user.pubkey(),
+    );
+    env.execute_ixs_with_signers(&[stake_ix], &[&user])
+        .succeeds()?
+        .assert_compute_units_snapshot("stake_tokens")?;
 
-    Ok(token_account)
+    Ok(())
 }
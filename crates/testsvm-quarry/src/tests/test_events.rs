@@ -0,0 +1,166 @@
+use anyhow::Result;
+use testsvm::prelude::*;
+
+use crate::quarry_mine;
+
+use super::common::{QuarryTestSetup, setup_quarry};
+
+#[test]
+fn test_stake_and_claim_events() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        rewarder,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+
+    let stake = quarry.stake_tokens(
+        &mut env,
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user,
+    )?;
+    let stake_events = stake.events::<quarry_mine::events::StakeEvent>()?;
+    assert_eq!(stake_events.len(), 1);
+    assert_eq!(stake_events[0].authority, user.pubkey());
+    assert_eq!(stake_events[0].amount, 100 * 10u64.pow(6));
+    // Events of other types are not returned
+    assert!(
+        stake
+            .events::<quarry_mine::events::ClaimEvent>()?
+            .is_empty()
+    );
+
+    env.advance_time(365 * 24 * 60 * 60);
+    quarry.update_quarry_rewards(&mut env)?;
+    let (create_ata_ix, user_rewards) = env.create_ata_ix(
+        "user_rewards",
+        &user.pubkey(),
+        &rewarder.mint_wrapper.reward_token_mint.key,
+    )?;
+    env.execute_ixs(&[create_ata_ix])?;
+
+    let claim = quarry.claim_rewards(
+        &mut env,
+        &rewarder,
+        &miner,
+        &miner_vault,
+        &user_rewards,
+        &user,
+    )?;
+    let claim_events = claim.events::<quarry_mine::events::ClaimEvent>()?;
+    assert_eq!(claim_events.len(), 1);
+    let claim_event = &claim_events[0];
+    assert_eq!(claim_event.authority, user.pubkey());
+    assert_eq!(
+        claim_event.rewards_token,
+        rewarder.mint_wrapper.reward_token_mint.key
+    );
+
+    // The event matches the balance changes of the claim
+    let claimed = claim.balances.token(&user_rewards.key).unwrap().change();
+    assert_eq!(claim_event.amount as i128, claimed);
+    let fees = claim
+        .balances
+        .token(&rewarder.claim_fee_token_account.key)
+        .unwrap()
+        .change();
+    assert_eq!(claim_event.fees as i128, fees);
+
+    Ok(())
+}
+
+#[test]
+fn test_events_before_failure() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+
+    // The stake succeeds and emits an event, but the transaction fails afterwards
+    let stake_ix = quarry.stake_tokens_ix(
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user.pubkey(),
+    );
+    let failing_ix = solana_sdk::system_instruction::transfer(
+        &user.pubkey(),
+        &env.default_fee_payer.pubkey(),
+        1_000_000 * 10u64.pow(9),
+    );
+    let err = env
+        .execute_ixs_with_signers(&[stake_ix, failing_ix], &[&user])
+        .unwrap_err();
+
+    let stake_events = err.events::<quarry_mine::events::StakeEvent>()?;
+    assert_eq!(stake_events.len(), 1);
+    assert_eq!(stake_events[0].amount, 100 * 10u64.pow(6));
+
+    // No state was changed by the failed transaction
+    let miner_account: quarry_mine::accounts::Miner = miner.load(&env)?;
+    assert_eq!(miner_account.balance, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_stake_logs() -> Result<()> {
+    let QuarryTestSetup {
+        mut env,
+        user,
+        quarry,
+        user_staked_tokens,
+        ..
+    } = setup_quarry()?;
+    let (miner, miner_vault) = quarry.create_miner(&mut env, "user", &user)?;
+
+    let stake_ix = quarry.stake_tokens_ix(
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        100 * 10u64.pow(6),
+        &user.pubkey(),
+    );
+    let assertions = env
+        .execute_ixs_with_signers(&[stake_ix], &[&user])
+        .succeeds()?;
+
+    assertions
+        .log_contains("Instruction: StakeTokens")?
+        .log_matches(r"^Program quarry_mine consumed \d+ of \d+ compute units$")?
+        .log_emitted_by(&quarry_mine::ID, 1, "Instruction: StakeTokens")?
+        .log_emitted_by(&anchor_spl::token::ID, 2, "Instruction: Transfer")?
+        .log_absent("Instruction: ClaimRewards")?;
+
+    // Logs are attributed to the depth they were emitted at
+    assert!(
+        assertions
+            .log_emitted_by(&anchor_spl::token::ID, 1, "Instruction: Transfer")
+            .is_err()
+    );
+
+    // Failed transactions can be matched on their labeled logs as well
+    let withdraw_ix = quarry.withdraw_tokens_ix(
+        &miner,
+        &miner_vault,
+        &user_staked_tokens,
+        200 * 10u64.pow(6),
+        &user.pubkey(),
+    );
+    env.execute_ixs_with_signers(&[withdraw_ix], &[&user])
+        .fails()?
+        .log_contains("Program quarry_mine failed")?;
+
+    Ok(())
+}
@@ -253,3 +253,34 @@ fn test_merge_miner_integration() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_seeded_quarry_addresses() -> Result<()> {
+    let setup = || -> Result<(TestSVM, TestRewarder)> {
+        let mut env = TestSVM::builder()
+            .keypair_seed(42)
+            .plugin(crate::setup_quarry_programs)
+            .build()?;
+        let authority = env.new_wallet("authority")?;
+        let rewarder = TestRewarder::new_rewarder(&mut env, "main", &authority)?;
+        Ok((env, rewarder))
+    };
+    let (first_env, first) = setup()?;
+    let (second_env, second) = setup()?;
+
+    // Every address is the same across runs with the same seed
+    assert_eq!(first.rewarder.key, second.rewarder.key);
+    assert_eq!(
+        first.mint_wrapper.reward_token_mint.key,
+        second.mint_wrapper.reward_token_mint.key
+    );
+    assert_eq!(first.authority, second.authority);
+    let wallets = |env: &TestSVM| {
+        let mut wallets = env.address_book.get_all_by_role_type("wallet");
+        wallets.sort();
+        wallets
+    };
+    assert_eq!(wallets(&first_env), wallets(&second_env));
+
+    Ok(())
+}
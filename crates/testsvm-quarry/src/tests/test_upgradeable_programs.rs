@@ -0,0 +1,107 @@
+use anyhow::Result;
+use solana_sdk::transaction::TransactionError;
+use testsvm::prelude::*;
+
+use crate::{TestRewarder, quarry_mine, quarry_mint_wrapper};
+
+const QUARRY_MINE_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../fixtures/programs/quarry_mine.so"
+);
+
+/// Deploys `quarry_mine` as an upgradeable program at its real address.
+fn init_upgradeable_environment() -> Result<(TestSVM, Keypair)> {
+    let mut env = TestSVM::builder()
+        .program_fixture("quarry_mint_wrapper", quarry_mint_wrapper::ID)
+        .build()?;
+    let upgrade_authority = env.new_wallet("upgrade_authority")?;
+    env.deploy_upgradeable_program(
+        "quarry_mine",
+        quarry_mine::ID,
+        QUARRY_MINE_PATH,
+        &upgrade_authority,
+    )?;
+    Ok((env, upgrade_authority))
+}
+
+#[test]
+fn test_upgrade_preserves_state() -> Result<()> {
+    let (mut env, upgrade_authority) = init_upgradeable_environment()?;
+    assert_eq!(
+        env.program_upgrade_authority(&quarry_mine::ID)?,
+        Some(upgrade_authority.pubkey())
+    );
+
+    // Create state with the original program
+    let authority = env.new_wallet("authority")?;
+    let rewarder = TestRewarder::new_rewarder(&mut env, "main", &authority)?;
+
+    // Upgrade the program in a later slot
+    env.advance_slots(1);
+    env.upgrade_program(quarry_mine::ID, QUARRY_MINE_PATH, &upgrade_authority)??;
+
+    // The upgraded program still handles accounts created before the upgrade
+    rewarder.set_annual_rewards_rate(&mut env, 1_000, &authority)?;
+    assert_eq!(rewarder.fetch_rewarder(&env)?.annual_rewards_rate, 1_000);
+
+    // Only the upgrade authority may upgrade the program
+    env.advance_slots(1);
+    let result = env.upgrade_program(quarry_mine::ID, QUARRY_MINE_PATH, &authority)?;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_set_authority_and_close() -> Result<()> {
+    let (mut env, upgrade_authority) = init_upgradeable_environment()?;
+    let new_authority = env.new_wallet("new_authority")?;
+
+    env.set_program_upgrade_authority(
+        quarry_mine::ID,
+        &upgrade_authority,
+        Some(&new_authority.pubkey()),
+    )?;
+    assert_eq!(
+        env.program_upgrade_authority(&quarry_mine::ID)?,
+        Some(new_authority.pubkey())
+    );
+
+    // The program cannot be closed in the slot it was deployed in
+    let recipient = Pubkey::new_unique();
+    assert!(
+        env.close_program(quarry_mine::ID, &new_authority, &recipient)?
+            .is_err()
+    );
+
+    // Expire the blockhash so the retry is not a duplicate of the failed transaction
+    env.advance_slots(1);
+    env.svm.expire_blockhash();
+    env.close_program(quarry_mine::ID, &new_authority, &recipient)??;
+    assert!(env.svm.get_balance(&recipient).unwrap() > 0);
+    assert!(env.program_upgrade_authority(&quarry_mine::ID).is_err());
+
+    // A closed program can no longer be invoked
+    let authority = env.new_wallet("authority")?;
+    assert!(TestRewarder::new_rewarder(&mut env, "main", &authority).is_err());
+    assert_eq!(
+        env.history.last().unwrap().result,
+        Err(TransactionError::InvalidProgramForExecution)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_make_immutable() -> Result<()> {
+    let (mut env, upgrade_authority) = init_upgradeable_environment()?;
+
+    env.set_program_upgrade_authority(quarry_mine::ID, &upgrade_authority, None)?;
+    assert_eq!(env.program_upgrade_authority(&quarry_mine::ID)?, None);
+
+    env.advance_slots(1);
+    let result = env.upgrade_program(quarry_mine::ID, QUARRY_MINE_PATH, &upgrade_authority)?;
+    assert!(result.is_err());
+
+    Ok(())
+}
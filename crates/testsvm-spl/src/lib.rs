@@ -161,7 +161,8 @@ impl TestSVMSPLHelpers for TestSVM {
         decimals: u8,
         authority: &Pubkey,
     ) -> Result<AccountRef<anchor_spl::token::Mint>> {
-        let mint = Keypair::new();
+        let label = format!("mint:{name}");
+        let mint = self.new_keypair(&label);
 
         let rent = self
             .svm
@@ -186,8 +187,7 @@ impl TestSVMSPLHelpers for TestSVM {
 
         // Add the mint to the address book
         let mint_pubkey = mint.pubkey();
-        let label = format!("mint:{name}");
-        self.address_book
+        self.address_book_mut()
             .add(mint_pubkey, label, RegisteredAddress::mint(mint_pubkey))?;
 
         self.execute_ixs_with_signers(&[create_account_ix, init_mint_ix], &[&mint])
@@ -208,7 +208,7 @@ impl TestSVMSPLHelpers for TestSVM {
         let ata = anchor_spl::associated_token::get_associated_token_address(owner, mint);
 
         // Add to address book
-        self.address_book.add(
+        self.address_book_mut().add(
             ata,
             label.to_string(),
             RegisteredAddress::ata(ata, *mint, *owner),
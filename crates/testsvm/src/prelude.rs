@@ -11,6 +11,9 @@
 
 // Core TestSVM types
 pub use anchor_spl;
-pub use testsvm_assertions::{TXErrorAssertions, TXResultAssertions, TXSuccessAssertions};
+pub use testsvm_assertions::{
+    AccountAssertions, BalanceChangeAssertions, LogAssertions, TXErrorAssertions,
+    TXResultAssertions, TXSuccessAssertions, TestSVMAccountAssertions, assert_account_eq,
+};
 pub use testsvm_core::prelude::*;
 pub use testsvm_spl::prelude::*;